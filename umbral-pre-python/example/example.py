@@ -12,6 +12,10 @@ signing_sk = umbral_pre.SecretKey.random()
 signer = umbral_pre.Signer(signing_sk)
 verifying_pk = signing_sk.public_key()
 
+# Make sure the key to be distributed to Ursulas and Bob for verification
+# is the one corresponding to the signer.
+assert signer.verifying_key_equals(verifying_pk)
+
 # Key Generation (on Bob's side)
 bob_sk = umbral_pre.SecretKey.random()
 bob_pk = bob_sk.public_key()
@@ -1,14 +1,22 @@
+// `pyo3` 0.14 generates `.as_deref()` calls for `Option<&T>` arguments of functions.
+#![allow(clippy::needless_option_as_deref)]
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use generic_array::GenericArray;
+
 use pyo3::class::basic::CompareOp;
 use pyo3::create_exception;
-use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
+use pyo3::exceptions::{PyException, PyKeyError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::pyclass::PyClass;
-use pyo3::types::{PyBytes, PyUnicode};
+use pyo3::types::{PyByteArray, PyBytes, PyDict, PyUnicode};
 use pyo3::wrap_pyfunction;
-use pyo3::PyObjectProtocol;
+use pyo3::{PyObjectProtocol, PySequenceProtocol};
 
 use umbral_pre::{
-    DeserializableFromArray, HasTypeName, RepresentableAsArray, SerializableToArray,
+    DeserializableFromArray, HasTypeName, RepresentableAsArray, SecretBox, SerializableToArray,
     SerializableToSecretArray,
 };
 
@@ -53,7 +61,7 @@ where
 {
     U::from_bytes(data)
         .map(T::from_backend)
-        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+        .map_err(deserialization_err)
 }
 
 fn hash<T, U>(obj: &T) -> PyResult<isize>
@@ -88,21 +96,40 @@ where
 }
 
 create_exception!(umbral, VerificationError, PyException);
+create_exception!(umbral, UnsupportedVersionError, PyValueError);
+
+fn deserialization_err(err: umbral_pre::DeserializationError) -> PyErr {
+    match err {
+        umbral_pre::DeserializationError::UnsupportedVersion(_) => {
+            UnsupportedVersionError::new_err(format!("{}", err))
+        }
+        _ => PyValueError::new_err(format!("{}", err)),
+    }
+}
+
+fn zeroized_err(type_name: &str) -> PyErr {
+    PyValueError::new_err(format!("{} has been zeroized", type_name))
+}
 
 #[pyclass(module = "umbral")]
 pub struct SecretKey {
-    backend: umbral_pre::SecretKey,
+    // `None` if the key has been zeroized.
+    backend: Option<umbral_pre::SecretKey>,
 }
 
-impl AsBackend<umbral_pre::SecretKey> for SecretKey {
-    fn as_backend(&self) -> &umbral_pre::SecretKey {
-        &self.backend
+impl SecretKey {
+    fn backend(&self) -> PyResult<&umbral_pre::SecretKey> {
+        self.backend
+            .as_ref()
+            .ok_or_else(|| zeroized_err("SecretKey"))
     }
 }
 
 impl FromBackend<umbral_pre::SecretKey> for SecretKey {
     fn from_backend(backend: umbral_pre::SecretKey) -> Self {
-        Self { backend }
+        Self {
+            backend: Some(backend),
+        }
     }
 }
 
@@ -110,19 +137,23 @@ impl FromBackend<umbral_pre::SecretKey> for SecretKey {
 impl SecretKey {
     #[staticmethod]
     pub fn random() -> Self {
-        Self {
-            backend: umbral_pre::SecretKey::random(),
-        }
+        Self::from_backend(umbral_pre::SecretKey::random())
     }
 
-    pub fn public_key(&self) -> PublicKey {
-        PublicKey {
-            backend: self.backend.public_key(),
-        }
+    pub fn public_key(&self) -> PyResult<PublicKey> {
+        Ok(PublicKey {
+            backend: self.backend()?.public_key(),
+        })
     }
 
-    pub fn to_secret_bytes(&self) -> PyResult<PyObject> {
-        to_secret_bytes(self)
+    pub fn to_secret_bytes(&self, py: Python) -> PyResult<PyObject> {
+        let serialized = self.backend()?.to_secret_array();
+        Ok(PyBytes::new(py, serialized.as_secret().as_slice()).into())
+    }
+
+    pub fn zeroize(&mut self) {
+        // The backend key is zeroized on drop.
+        self.backend = None;
     }
 
     #[staticmethod]
@@ -139,7 +170,7 @@ impl SecretKey {
 #[pyproto]
 impl PyObjectProtocol for SecretKey {
     fn __str__(&self) -> PyResult<String> {
-        Ok(format!("{}", self.backend))
+        Ok(format!("{}", self.backend()?))
     }
 }
 
@@ -174,6 +205,11 @@ impl SecretKeyFactory {
         umbral_pre::SecretKeyFactory::seed_size()
     }
 
+    #[staticmethod]
+    pub fn derivation_version() -> u32 {
+        umbral_pre::SecretKeyFactory::DERIVATION_VERSION
+    }
+
     #[staticmethod]
     pub fn from_secure_randomness(seed: &[u8]) -> PyResult<SecretKeyFactory> {
         umbral_pre::SecretKeyFactory::from_secure_randomness(seed)
@@ -186,9 +222,14 @@ impl SecretKeyFactory {
     pub fn make_key(&self, label: &[u8]) -> PyResult<SecretKey> {
         self.backend
             .make_key(label)
-            .map(|backend_sk| SecretKey {
-                backend: backend_sk,
-            })
+            .map(SecretKey::from_backend)
+            .map_err(|err| PyValueError::new_err(format!("{}", err)))
+    }
+
+    pub fn receiving_keypair_by_label(&self, label: &[u8]) -> PyResult<(SecretKey, PublicKey)> {
+        self.backend
+            .receiving_keypair_by_label(label)
+            .map(|(sk, pk)| (SecretKey::from_backend(sk), PublicKey { backend: pk }))
             .map_err(|err| PyValueError::new_err(format!("{}", err)))
     }
 
@@ -202,6 +243,12 @@ impl SecretKeyFactory {
         to_secret_bytes(self)
     }
 
+    pub fn to_secret_bytes_wrapper(&self) -> SecretBytes {
+        SecretBytes {
+            backend: self.backend.to_secret_array(),
+        }
+    }
+
     #[staticmethod]
     pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
         from_bytes(data)
@@ -218,6 +265,177 @@ impl PyObjectProtocol for SecretKeyFactory {
     fn __str__(&self) -> PyResult<String> {
         Ok(format!("{}", self.backend))
     }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("{}", self.backend))
+    }
+}
+
+/// Secret bytes that can only be accessed with an explicit `expose_secret()` call.
+#[pyclass(module = "umbral")]
+pub struct SecretBytes {
+    backend:
+        SecretBox<GenericArray<u8, <umbral_pre::SecretKeyFactory as RepresentableAsArray>::Size>>,
+}
+
+#[pymethods]
+impl SecretBytes {
+    pub fn expose_secret(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.backend.as_secret()).into()
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for SecretBytes {
+    fn __str__(&self) -> PyResult<String> {
+        Ok("SecretBytes:...".into())
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok("SecretBytes:...".into())
+    }
+}
+
+#[pyclass(module = "umbral")]
+#[derive(Default)]
+pub struct Keystore {
+    backend: umbral_pre::Keystore,
+}
+
+#[pymethods]
+impl Keystore {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, secret_key: &SecretKey) -> PyResult<()> {
+        self.backend.add(name, secret_key.backend()?);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> PyResult<SecretKey> {
+        self.backend
+            .get(name)
+            .map(|backend_sk| SecretKey::from_backend(backend_sk.clone()))
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+    }
+
+    pub fn remove(&mut self, name: &str) -> PyResult<()> {
+        self.backend
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.backend.names().collect()
+    }
+
+    pub fn encrypt(&self, py: Python, passphrase: &[u8]) -> PyResult<PyObject> {
+        self.backend
+            .encrypt(passphrase)
+            .map(|data| PyBytes::new(py, &data).into())
+            .map_err(|err| PyValueError::new_err(format!("{}", err)))
+    }
+
+    #[staticmethod]
+    pub fn decrypt(data: &[u8], passphrase: &[u8]) -> PyResult<Self> {
+        umbral_pre::Keystore::decrypt(data, passphrase)
+            .map(|backend| Self { backend })
+            .map_err(|err| PyValueError::new_err(format!("{}", err)))
+    }
+}
+
+#[pyproto]
+impl PySequenceProtocol for Keystore {
+    fn __len__(&self) -> usize {
+        self.backend.len()
+    }
+}
+
+#[pyclass(module = "umbral")]
+pub struct KeyRecord {
+    backend: umbral_pre::KeyRecord,
+}
+
+fn current_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[pymethods]
+impl KeyRecord {
+    #[new]
+    pub fn new(public_key: &PublicKey, label: &str, created_at: Option<u64>) -> Self {
+        Self {
+            backend: umbral_pre::KeyRecord::new(
+                &public_key.backend,
+                label,
+                created_at.unwrap_or_else(current_timestamp),
+            ),
+        }
+    }
+
+    #[staticmethod]
+    pub fn from_secret_key(
+        secret_key: &SecretKey,
+        label: &str,
+        passphrase: &[u8],
+        created_at: Option<u64>,
+    ) -> PyResult<Self> {
+        umbral_pre::KeyRecord::from_secret_key(
+            secret_key.backend()?,
+            label,
+            created_at.unwrap_or_else(current_timestamp),
+            passphrase,
+        )
+        .map(|backend| Self { backend })
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+    }
+
+    #[getter]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            backend: self.backend.public_key(),
+        }
+    }
+
+    #[getter]
+    pub fn label(&self) -> &str {
+        self.backend.label()
+    }
+
+    #[getter]
+    pub fn created_at(&self) -> u64 {
+        self.backend.created_at()
+    }
+
+    #[getter]
+    pub fn has_secret_key(&self) -> bool {
+        self.backend.has_secret_key()
+    }
+
+    pub fn secret_key(&self, passphrase: &[u8]) -> PyResult<Option<SecretKey>> {
+        self.backend
+            .secret_key(passphrase)
+            .map(|maybe_sk| maybe_sk.map(SecretKey::from_backend))
+            .map_err(|err| PyValueError::new_err(format!("{}", err)))
+    }
+
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        umbral_pre::KeyRecord::from_bytes(data)
+            .map(|backend| Self { backend })
+            .map_err(deserialization_err)
+    }
+
+    fn __bytes__(&self) -> PyResult<PyObject> {
+        let serialized = self.backend.to_bytes();
+        Python::with_gil(|py| -> PyResult<PyObject> { Ok(PyBytes::new(py, &serialized).into()) })
+    }
 }
 
 #[pyclass(module = "umbral")]
@@ -272,41 +490,51 @@ impl PyObjectProtocol for PublicKey {
 
 #[pyclass(module = "umbral")]
 pub struct Signer {
-    backend: umbral_pre::Signer,
+    // `None` if the signer has been zeroized.
+    backend: Option<umbral_pre::Signer>,
 }
 
-impl AsBackend<umbral_pre::Signer> for Signer {
-    fn as_backend(&self) -> &umbral_pre::Signer {
-        &self.backend
+impl Signer {
+    fn backend(&self) -> PyResult<&umbral_pre::Signer> {
+        self.backend.as_ref().ok_or_else(|| zeroized_err("Signer"))
     }
 }
 
 #[pymethods]
 impl Signer {
     #[new]
-    pub fn new(sk: &SecretKey) -> Self {
-        Self {
-            backend: umbral_pre::Signer::new(&sk.backend),
-        }
+    pub fn new(sk: &SecretKey) -> PyResult<Self> {
+        Ok(Self {
+            backend: Some(umbral_pre::Signer::new(sk.backend()?)),
+        })
     }
 
-    pub fn sign(&self, message: &[u8]) -> Signature {
-        Signature {
-            backend: self.backend.sign(message),
-        }
+    pub fn sign(&self, message: &[u8]) -> PyResult<Signature> {
+        Ok(Signature {
+            backend: self.backend()?.sign(message),
+        })
     }
 
-    pub fn verifying_key(&self) -> PublicKey {
-        PublicKey {
-            backend: self.backend.verifying_key(),
-        }
+    pub fn verifying_key(&self) -> PyResult<PublicKey> {
+        Ok(PublicKey {
+            backend: self.backend()?.verifying_key(),
+        })
+    }
+
+    pub fn verifying_key_equals(&self, verifying_pk: &PublicKey) -> PyResult<bool> {
+        Ok(self.backend()?.verifying_key_equals(&verifying_pk.backend))
+    }
+
+    pub fn zeroize(&mut self) {
+        // The backend secret key is zeroized on drop.
+        self.backend = None;
     }
 }
 
 #[pyproto]
 impl PyObjectProtocol for Signer {
     fn __str__(&self) -> PyResult<String> {
-        Ok(format!("{}", self.backend))
+        Ok(format!("{}", self.backend()?))
     }
 }
 
@@ -432,6 +660,43 @@ pub fn encrypt(
         .map_err(|err| PyValueError::new_err(format!("{}", err)))
 }
 
+#[pyfunction]
+pub fn encrypt_detached(
+    py: Python,
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+) -> PyResult<(Capsule, PyObject, PyObject)> {
+    umbral_pre::encrypt_detached(&delegating_pk.backend, plaintext)
+        .map(|(backend_capsule, ciphertext_body, tag)| {
+            (
+                Capsule {
+                    backend: backend_capsule,
+                },
+                PyBytes::new(py, &ciphertext_body).into(),
+                PyBytes::new(py, &tag).into(),
+            )
+        })
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn decrypt_original_detached(
+    py: Python,
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext_body: &[u8],
+    tag: &[u8],
+) -> PyResult<PyObject> {
+    umbral_pre::decrypt_original_detached(
+        delegating_sk.backend()?,
+        &capsule.backend,
+        ciphertext_body,
+        tag,
+    )
+    .map(|plaintext| PyBytes::new(py, &plaintext).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
 #[pyfunction]
 pub fn decrypt_original(
     py: Python,
@@ -439,13 +704,65 @@ pub fn decrypt_original(
     capsule: &Capsule,
     ciphertext: &[u8],
 ) -> PyResult<PyObject> {
-    umbral_pre::decrypt_original(&delegating_sk.backend, &capsule.backend, &ciphertext)
+    umbral_pre::decrypt_original(delegating_sk.backend()?, &capsule.backend, ciphertext)
         .map(|plaintext| PyBytes::new(py, &plaintext).into())
         .map_err(|err| PyValueError::new_err(format!("{}", err)))
 }
 
+#[pyfunction]
+pub fn decrypt_original_into(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: &[u8],
+    out: &PyByteArray,
+    offset: Option<usize>,
+) -> PyResult<usize> {
+    let offset = offset.unwrap_or(0);
+    if offset > out.len() {
+        return Err(PyValueError::new_err(format!(
+            "The offset {} is outside of the output buffer of {} bytes",
+            offset,
+            out.len()
+        )));
+    }
+    let delegating_sk = delegating_sk.backend()?;
+    // Safe since no Python code is executed while the slice is alive.
+    let out_bytes = unsafe { out.as_bytes_mut() };
+    umbral_pre::decrypt_original_into(
+        delegating_sk,
+        &capsule.backend,
+        ciphertext,
+        &mut out_bytes[offset..],
+    )
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn plaintext_size(ciphertext_size: usize) -> Option<usize> {
+    umbral_pre::plaintext_size(ciphertext_size)
+}
+
+#[pyfunction]
+pub fn same_capsule(capsule_a: &Capsule, capsule_b: &Capsule) -> bool {
+    umbral_pre::same_capsule(&capsule_a.backend, &capsule_b.backend)
+}
+
+#[pyfunction]
+pub fn is_owner(delegating_sk: &SecretKey, capsule: &Capsule, ciphertext: &[u8]) -> PyResult<bool> {
+    Ok(umbral_pre::is_owner(
+        delegating_sk.backend()?,
+        &capsule.backend,
+        ciphertext,
+    ))
+}
+
+#[pyfunction]
+pub fn self_encrypt_decrypt(delegating_sk: &SecretKey) -> PyResult<bool> {
+    Ok(umbral_pre::self_encrypt_decrypt(delegating_sk.backend()?))
+}
+
 #[pyclass(module = "umbral")]
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct KeyFrag {
     backend: umbral_pre::KeyFrag,
 }
@@ -482,6 +799,10 @@ impl KeyFrag {
             })
     }
 
+    pub fn commitment(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.backend.commitment().to_array().as_slice()).into()
+    }
+
     #[staticmethod]
     pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
         from_bytes(data)
@@ -533,6 +854,10 @@ impl VerifiedKeyFrag {
             .map_err(|err| PyValueError::new_err(format!("{}", err)))
     }
 
+    pub fn commitment(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.backend.commitment().to_array().as_slice()).into()
+    }
+
     #[staticmethod]
     pub fn serialized_size() -> usize {
         umbral_pre::VerifiedKeyFrag::serialized_size()
@@ -558,9 +883,25 @@ impl PyObjectProtocol for VerifiedKeyFrag {
     }
 }
 
+fn check_labels<T: Ord>(labels: &[T], shares: usize) -> PyResult<()> {
+    if labels.len() != shares {
+        return Err(PyValueError::new_err(format!(
+            "The number of labels ({}) must be equal to the number of shares ({})",
+            labels.len(),
+            shares
+        )));
+    }
+    let unique_labels: BTreeSet<&T> = labels.iter().collect();
+    if unique_labels.len() != labels.len() {
+        return Err(PyValueError::new_err("Labels must be distinct"));
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 #[pyfunction]
 pub fn generate_kfrags(
+    py: Python,
     delegating_sk: &SecretKey,
     receiving_pk: &PublicKey,
     signer: &Signer,
@@ -568,22 +909,103 @@ pub fn generate_kfrags(
     shares: usize,
     sign_delegating_key: bool,
     sign_receiving_key: bool,
-) -> Vec<VerifiedKeyFrag> {
-    let backend_kfrags = umbral_pre::generate_kfrags(
-        &delegating_sk.backend,
+    id_seed: Option<&[u8]>,
+    id_labels: Option<Vec<&[u8]>>,
+    node_labels: Option<Vec<&str>>,
+) -> PyResult<PyObject> {
+    let id_labels = match &node_labels {
+        None => id_labels,
+        Some(labels) => {
+            if id_labels.is_some() {
+                return Err(PyValueError::new_err(
+                    "id_labels and node_labels cannot be used together",
+                ));
+            }
+            check_labels(labels, shares)?;
+            // If the seed is given, the node labels are used to derive the IDs.
+            id_seed.map(|_| labels.iter().map(|label| label.as_bytes()).collect())
+        }
+    };
+
+    let backend_kfrags = match id_labels {
+        None => {
+            if id_seed.is_some() {
+                return Err(PyValueError::new_err(
+                    "id_seed can only be used together with id_labels or node_labels",
+                ));
+            }
+            umbral_pre::generate_kfrags(
+                delegating_sk.backend()?,
+                &receiving_pk.backend,
+                signer.backend()?,
+                threshold,
+                shares,
+                sign_delegating_key,
+                sign_receiving_key,
+            )
+        }
+        Some(labels) => {
+            check_labels(&labels, shares)?;
+            umbral_pre::generate_labeled_kfrags(
+                delegating_sk.backend()?,
+                &receiving_pk.backend,
+                signer.backend()?,
+                threshold,
+                id_seed.unwrap_or(&[]),
+                &labels,
+                sign_delegating_key,
+                sign_receiving_key,
+            )
+            .map_err(|err| PyValueError::new_err(format!("{}", err)))?
+        }
+    };
+
+    let vkfrags = backend_kfrags
+        .iter()
+        .cloned()
+        .map(|val| VerifiedKeyFrag { backend: val });
+
+    match node_labels {
+        None => Ok(vkfrags.collect::<Vec<_>>().into_py(py)),
+        Some(labels) => {
+            let buckets = PyDict::new(py);
+            for (label, vkfrag) in labels.iter().zip(vkfrags) {
+                buckets.set_item(label, Py::new(py, vkfrag)?)?;
+            }
+            Ok(buckets.into())
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+pub fn generate_deterministic_kfrags(
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signer: &Signer,
+    threshold: usize,
+    delegation_seed: &[u8],
+    labels: Vec<&[u8]>,
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+) -> PyResult<Vec<VerifiedKeyFrag>> {
+    check_labels(&labels, labels.len())?;
+    let backend_kfrags = umbral_pre::generate_deterministic_kfrags(
+        delegating_sk.backend()?,
         &receiving_pk.backend,
-        &signer.backend,
+        signer.backend()?,
         threshold,
-        shares,
+        delegation_seed,
+        &labels,
         sign_delegating_key,
         sign_receiving_key,
-    );
-
-    backend_kfrags
+    )
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))?;
+    Ok(backend_kfrags
         .iter()
         .cloned()
         .map(|val| VerifiedKeyFrag { backend: val })
-        .collect()
+        .collect())
 }
 
 #[pyclass(module = "umbral")]
@@ -626,6 +1048,23 @@ impl CapsuleFrag {
             })
     }
 
+    pub fn verify_signature_only(
+        &self,
+        verifying_pk: &PublicKey,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+    ) -> bool {
+        self.backend.verify_signature_only(
+            &verifying_pk.backend,
+            &delegating_pk.backend,
+            &receiving_pk.backend,
+        )
+    }
+
+    pub fn is_for_receiver(&self, receiving_sk: &SecretKey) -> PyResult<bool> {
+        Ok(self.backend.is_for_receiver(receiving_sk.backend()?))
+    }
+
     #[staticmethod]
     pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
         from_bytes(data)
@@ -692,6 +1131,10 @@ impl VerifiedCapsuleFrag {
             .map_err(|err| PyValueError::new_err(format!("{}", err)))
     }
 
+    pub fn is_for_receiver(&self, receiving_sk: &SecretKey) -> PyResult<bool> {
+        Ok(self.backend.is_for_receiver(receiving_sk.backend()?))
+    }
+
     #[staticmethod]
     pub fn serialized_size() -> usize {
         umbral_pre::VerifiedCapsuleFrag::serialized_size()
@@ -702,44 +1145,932 @@ impl VerifiedCapsuleFrag {
     }
 }
 
-#[pyfunction]
-pub fn reencrypt(capsule: &Capsule, kfrag: &VerifiedKeyFrag) -> VerifiedCapsuleFrag {
-    let backend_vcfrag = umbral_pre::reencrypt(&capsule.backend, &kfrag.backend);
-    VerifiedCapsuleFrag {
-        backend: backend_vcfrag,
-    }
+#[pyclass(module = "umbral")]
+pub struct CapsuleFragVerifier {
+    backend: umbral_pre::CapsuleFragVerifier,
 }
 
-#[pyfunction]
-pub fn decrypt_reencrypted(
-    py: Python,
-    receiving_sk: &SecretKey,
-    delegating_pk: &PublicKey,
-    capsule: &Capsule,
-    verified_cfrags: Vec<VerifiedCapsuleFrag>,
-    ciphertext: &[u8],
-) -> PyResult<PyObject> {
-    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
-        .iter()
-        .cloned()
+#[pymethods]
+impl CapsuleFragVerifier {
+    #[new]
+    pub fn new(
+        capsule: &Capsule,
+        verifying_pk: &PublicKey,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+    ) -> Self {
+        Self {
+            backend: umbral_pre::CapsuleFragVerifier::new(
+                &capsule.backend,
+                &verifying_pk.backend,
+                &delegating_pk.backend,
+                &receiving_pk.backend,
+            ),
+        }
+    }
+
+    pub fn verify(&self, cfrag: &CapsuleFrag) -> PyResult<VerifiedCapsuleFrag> {
+        self.backend
+            .verify(&cfrag.backend)
+            .map_err(|err| VerificationError::new_err(format!("{}", err)))
+            .map(|backend_vcfrag| VerifiedCapsuleFrag {
+                backend: backend_vcfrag,
+            })
+    }
+}
+
+/// The default number of processed items between invocations of a progress callback.
+const DEFAULT_PROGRESS_EVERY: usize = 100;
+
+#[pyfunction]
+pub fn verify_same_message(
+    py: Python,
+    signatures: Vec<(PyRef<Signature>, PyRef<PublicKey>)>,
+    message: &[u8],
+    progress: Option<PyObject>,
+    progress_every: Option<usize>,
+) -> PyResult<Vec<bool>> {
+    let backend_signatures: Vec<(umbral_pre::Signature, umbral_pre::PublicKey)> = signatures
+        .iter()
+        .map(|(signature, verifying_pk)| (signature.backend.clone(), verifying_pk.backend))
+        .collect();
+
+    let progress = match progress {
+        None => {
+            return Ok(py.allow_threads(|| {
+                umbral_pre::verify_same_message(&backend_signatures, message).into_vec()
+            }))
+        }
+        Some(progress) => progress,
+    };
+
+    let progress_every = progress_every.unwrap_or(DEFAULT_PROGRESS_EVERY);
+    if progress_every == 0 {
+        return Err(PyValueError::new_err("`progress_every` must be positive"));
+    }
+
+    // The verification runs with the GIL released, so it has to be re-acquired
+    // to call the callback. If the callback raises, it is not called again,
+    // and the exception is re-raised after the verification is finished.
+    let mut callback_error: Option<PyErr> = None;
+    let results = py.allow_threads(|| {
+        umbral_pre::verify_same_message_with_progress(
+            &backend_signatures,
+            message,
+            progress_every,
+            |done, total| {
+                if callback_error.is_none() {
+                    Python::with_gil(|py| {
+                        if let Err(err) = progress.call1(py, (done, total)) {
+                            callback_error = Some(err);
+                        }
+                    })
+                }
+            },
+        )
+    });
+
+    match callback_error {
+        Some(err) => Err(err),
+        None => Ok(results.into_vec()),
+    }
+}
+
+#[pyfunction]
+pub fn check_kfrags_consistency(kfrags: Vec<VerifiedKeyFrag>) -> PyResult<()> {
+    let backend_kfrags: Vec<umbral_pre::VerifiedKeyFrag> = kfrags
+        .iter()
+        .cloned()
+        .map(|vkfrag| vkfrag.backend)
+        .collect();
+    umbral_pre::check_kfrags_consistency(&backend_kfrags)
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn delegations_equal(kfrags_a: Vec<VerifiedKeyFrag>, kfrags_b: Vec<VerifiedKeyFrag>) -> bool {
+    let backend_kfrags_a: Vec<umbral_pre::VerifiedKeyFrag> = kfrags_a
+        .iter()
+        .cloned()
+        .map(|vkfrag| vkfrag.backend)
+        .collect();
+    let backend_kfrags_b: Vec<umbral_pre::VerifiedKeyFrag> = kfrags_b
+        .iter()
+        .cloned()
+        .map(|vkfrag| vkfrag.backend)
+        .collect();
+    umbral_pre::delegations_equal(&backend_kfrags_a, &backend_kfrags_b)
+}
+
+#[pyfunction]
+pub fn validate_grant(
+    py: Python,
+    delegating_sk: &SecretKey,
+    receiving_key: &PyAny,
+    signer: &Signer,
+    kfrags: Vec<VerifiedKeyFrag>,
+    threshold: usize,
+) -> PyResult<bool> {
+    let (receiving_pk, maybe_receiving_sk) =
+        if let Ok(receiving_sk) = receiving_key.extract::<PyRef<'_, SecretKey>>() {
+            let receiving_sk = receiving_sk.backend()?.clone();
+            (receiving_sk.public_key(), Some(receiving_sk))
+        } else if let Ok(receiving_pk) = receiving_key.extract::<PyRef<'_, PublicKey>>() {
+            (receiving_pk.backend, None)
+        } else {
+            return Err(PyTypeError::new_err(
+                "receiving_key must be a SecretKey or a PublicKey",
+            ));
+        };
+    let delegating_sk = delegating_sk.backend()?;
+    let signer = signer.backend()?;
+    let backend_kfrags: Vec<umbral_pre::VerifiedKeyFrag> =
+        kfrags.into_iter().map(|vkfrag| vkfrag.backend).collect();
+    Ok(py.allow_threads(|| {
+        umbral_pre::validate_grant(
+            delegating_sk,
+            &receiving_pk,
+            maybe_receiving_sk.as_ref(),
+            signer,
+            &backend_kfrags,
+            threshold,
+        )
+    }))
+}
+
+#[pyfunction]
+pub fn verify_cfrag_against_commitment(
+    cfrag: &CapsuleFrag,
+    commitment: &[u8],
+    capsule: &Capsule,
+) -> PyResult<bool> {
+    let backend_commitment = umbral_pre::KeyFragCommitment::from_bytes(commitment)
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))?;
+    Ok(cfrag
+        .backend
+        .verify_against_commitment(&capsule.backend, &backend_commitment))
+}
+
+#[pyfunction]
+pub fn reencrypt(capsule: &Capsule, kfrag: &VerifiedKeyFrag) -> VerifiedCapsuleFrag {
+    let backend_vcfrag = umbral_pre::reencrypt(&capsule.backend, &kfrag.backend);
+    VerifiedCapsuleFrag {
+        backend: backend_vcfrag,
+    }
+}
+
+#[derive(FromPyObject)]
+pub enum AnyKeyFrag {
+    Verified(VerifiedKeyFrag),
+    Unverified(KeyFrag),
+}
+
+#[pyfunction]
+pub fn reencrypt_multi(
+    py: Python,
+    capsule: &Capsule,
+    kfrags: Vec<AnyKeyFrag>,
+    verifying_pk: Option<&PublicKey>,
+    delegating_pk: Option<&PublicKey>,
+    receiving_pk: Option<&PublicKey>,
+) -> PyResult<Vec<PyObject>> {
+    let verifying_pk = verifying_pk.map(|pk| pk.backend);
+    if verifying_pk.is_none()
+        && kfrags
+            .iter()
+            .any(|kfrag| matches!(kfrag, AnyKeyFrag::Unverified(_)))
+    {
+        return Err(PyValueError::new_err(
+            "`verifying_pk` is required to verify unverified key fragments",
+        ));
+    }
+    let delegating_pk = delegating_pk.map(|pk| pk.backend);
+    let receiving_pk = receiving_pk.map(|pk| pk.backend);
+
+    // Each key fragment is processed independently,
+    // so a failed verification of one of them does not affect the others.
+    let results: Vec<_> = py.allow_threads(|| {
+        kfrags
+            .into_iter()
+            .map(|kfrag| {
+                let vkfrag = match kfrag {
+                    AnyKeyFrag::Verified(vkfrag) => vkfrag.backend,
+                    AnyKeyFrag::Unverified(kfrag) => kfrag.backend.verify(
+                        // Checked above
+                        verifying_pk.as_ref().unwrap(),
+                        delegating_pk.as_ref(),
+                        receiving_pk.as_ref(),
+                    )?,
+                };
+                Ok(umbral_pre::reencrypt(&capsule.backend, &vkfrag))
+            })
+            .collect()
+    });
+
+    Ok(results
+        .into_iter()
+        .map(
+            |result: Result<_, umbral_pre::KeyFragVerificationError>| match result {
+                Ok(backend_vcfrag) => VerifiedCapsuleFrag {
+                    backend: backend_vcfrag,
+                }
+                .into_py(py),
+                Err(err) => VerificationError::new_err(format!("{}", err)).into_py(py),
+            },
+        )
+        .collect())
+}
+
+#[pyfunction]
+pub fn reencrypt_into(
+    capsule: &Capsule,
+    kfrag: &VerifiedKeyFrag,
+    out: &PyByteArray,
+    offset: Option<usize>,
+) -> PyResult<()> {
+    let offset = offset.unwrap_or(0);
+    let size = umbral_pre::VerifiedCapsuleFrag::serialized_size();
+    if offset > out.len() || out.len() - offset < size {
+        return Err(PyValueError::new_err(format!(
+            "The output buffer of {} bytes cannot fit {} bytes at offset {}",
+            out.len(),
+            size,
+            offset
+        )));
+    }
+    let backend_vcfrag = umbral_pre::reencrypt(&capsule.backend, &kfrag.backend);
+    // Safe since no Python code is executed while the slice is alive.
+    let out_bytes = unsafe { out.as_bytes_mut() };
+    out_bytes[offset..offset + size].copy_from_slice(&backend_vcfrag.to_array());
+    Ok(())
+}
+
+#[pyfunction]
+pub fn decrypt_reencrypted(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    ciphertext: &[u8],
+) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
         .map(|vcfrag| vcfrag.backend)
         .collect();
     umbral_pre::decrypt_reencrypted(
-        &receiving_sk.backend,
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+        ciphertext,
+    )
+    .map(|plaintext| PyBytes::new(py, &plaintext).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn decrypt_reencrypted_detached(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    ciphertext_body: &[u8],
+    tag: &[u8],
+) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    umbral_pre::decrypt_reencrypted_detached(
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+        ciphertext_body,
+        tag,
+    )
+    .map(|plaintext| PyBytes::new(py, &plaintext).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn decrypt_reencrypted_into(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    ciphertext: &[u8],
+    out: &PyByteArray,
+    offset: Option<usize>,
+) -> PyResult<usize> {
+    let offset = offset.unwrap_or(0);
+    if offset > out.len() {
+        return Err(PyValueError::new_err(format!(
+            "The offset {} is outside of the output buffer of {} bytes",
+            offset,
+            out.len()
+        )));
+    }
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    let receiving_sk = receiving_sk.backend()?;
+    // Safe since no Python code is executed while the slice is alive.
+    let out_bytes = unsafe { out.as_bytes_mut() };
+    umbral_pre::decrypt_reencrypted_into(
+        receiving_sk,
         &delegating_pk.backend,
         &capsule.backend,
         &backend_cfrags,
         ciphertext,
+        &mut out_bytes[offset..],
+    )
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[derive(FromPyObject)]
+pub enum AnyCapsuleFrag {
+    Verified(VerifiedCapsuleFrag),
+    Unverified(CapsuleFrag),
+}
+
+#[pyfunction]
+pub fn open(
+    py: Python,
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: &[u8],
+    cfrags: Option<Vec<AnyCapsuleFrag>>,
+    delegating_pk: Option<&PublicKey>,
+    verifying_pk: Option<&PublicKey>,
+) -> PyResult<PyObject> {
+    let cfrags = match cfrags {
+        None => return decrypt_original(py, decrypting_sk, capsule, ciphertext),
+        Some(cfrags) => cfrags,
+    };
+
+    let delegating_pk = delegating_pk.ok_or_else(|| {
+        PyValueError::new_err("`delegating_pk` is required to decrypt with capsule fragments")
+    })?;
+    let receiving_pk = PublicKey {
+        backend: decrypting_sk.backend()?.public_key(),
+    };
+
+    let verified_cfrags = cfrags
+        .into_iter()
+        .map(|cfrag| match cfrag {
+            AnyCapsuleFrag::Verified(vcfrag) => Ok(vcfrag),
+            AnyCapsuleFrag::Unverified(cfrag) => {
+                let verifying_pk = verifying_pk.ok_or_else(|| {
+                    PyValueError::new_err(
+                        "`verifying_pk` is required to verify unverified capsule fragments",
+                    )
+                })?;
+                cfrag.verify(capsule, verifying_pk, delegating_pk, &receiving_pk)
+            }
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    decrypt_reencrypted(
+        py,
+        decrypting_sk,
+        delegating_pk,
+        capsule,
+        verified_cfrags,
+        ciphertext,
+    )
+}
+
+#[pyfunction]
+pub fn derive_symmetric_key(py: Python, delegating_pk: &PublicKey) -> (Capsule, PyObject) {
+    let (backend_capsule, key) = umbral_pre::derive_symmetric_key(&delegating_pk.backend);
+    (
+        Capsule {
+            backend: backend_capsule,
+        },
+        PyBytes::new(py, key.as_secret()).into(),
+    )
+}
+
+#[pyfunction]
+pub fn open_symmetric_key(
+    py: Python,
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+) -> PyResult<PyObject> {
+    let key = umbral_pre::open_symmetric_key(delegating_sk.backend()?, &capsule.backend);
+    Ok(PyBytes::new(py, key.as_secret()).into())
+}
+
+#[pyfunction]
+pub fn open_reencrypted_symmetric_key(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    umbral_pre::open_reencrypted_symmetric_key(
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+    )
+    .map(|key| PyBytes::new(py, key.as_secret()).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn serialize_cfrags_compact(py: Python, cfrags: Vec<AnyCapsuleFrag>) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::CapsuleFrag> = cfrags
+        .into_iter()
+        .map(|cfrag| match cfrag {
+            AnyCapsuleFrag::Verified(vcfrag) => vcfrag.backend.unverify(),
+            AnyCapsuleFrag::Unverified(cfrag) => cfrag.backend,
+        })
+        .collect();
+    umbral_pre::serialize_cfrags_compact(&backend_cfrags)
+        .map(|data| PyBytes::new(py, &data).into())
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn deserialize_cfrags_compact(data: &[u8]) -> PyResult<Vec<CapsuleFrag>> {
+    umbral_pre::deserialize_cfrags_compact(data)
+        .map(|cfrags| {
+            cfrags
+                .iter()
+                .cloned()
+                .map(|backend| CapsuleFrag { backend })
+                .collect()
+        })
+        .map_err(deserialization_err)
+}
+
+#[pyfunction]
+pub fn parse_cfrags(data: &[u8]) -> PyResult<Vec<CapsuleFrag>> {
+    umbral_pre::parse_cfrags(data)
+        .map(|cfrags| {
+            cfrags
+                .iter()
+                .cloned()
+                .map(|backend| CapsuleFrag { backend })
+                .collect()
+        })
+        .map_err(deserialization_err)
+}
+
+#[pyfunction]
+pub fn encrypt_record(
+    py: Python,
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+    record_id: &[u8],
+    schema_version: u32,
+) -> PyResult<(Capsule, PyObject)> {
+    umbral_pre::encrypt_record(&delegating_pk.backend, plaintext, record_id, schema_version)
+        .map(|(backend_capsule, ciphertext)| {
+            (
+                Capsule {
+                    backend: backend_capsule,
+                },
+                PyBytes::new(py, &ciphertext).into(),
+            )
+        })
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn decrypt_original_record(
+    py: Python,
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: &[u8],
+    record_id: &[u8],
+    schema_version: u32,
+) -> PyResult<PyObject> {
+    umbral_pre::decrypt_original_record(
+        delegating_sk.backend()?,
+        &capsule.backend,
+        ciphertext,
+        record_id,
+        schema_version,
     )
     .map(|plaintext| PyBytes::new(py, &plaintext).into())
     .map_err(|err| PyValueError::new_err(format!("{}", err)))
 }
 
+#[allow(clippy::too_many_arguments)]
+#[pyfunction]
+pub fn decrypt_reencrypted_record(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    ciphertext: &[u8],
+    record_id: &[u8],
+    schema_version: u32,
+) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    umbral_pre::decrypt_reencrypted_record(
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+        ciphertext,
+        record_id,
+        schema_version,
+    )
+    .map(|plaintext| PyBytes::new(py, &plaintext).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+fn reencryption_error_code(err: &umbral_pre::ReencryptionError) -> &'static str {
+    use umbral_pre::{DecryptionError, OpenReencryptedError, ReencryptionError};
+    match err {
+        ReencryptionError::OnOpen(err) => match err {
+            OpenReencryptedError::NoCapsuleFrags => "NoCapsuleFrags",
+            OpenReencryptedError::MismatchedCapsuleFrags => "MismatchedCapsuleFrags",
+            OpenReencryptedError::RepeatingCapsuleFrags => "RepeatingCapsuleFrags",
+            OpenReencryptedError::ZeroHash => "ZeroHash",
+            OpenReencryptedError::ValidationFailed => "ValidationFailed",
+            OpenReencryptedError::ReceiverMismatch => "ReceiverMismatch",
+            _ => "Unknown",
+        },
+        ReencryptionError::OnDecryption(err) => match err {
+            DecryptionError::CiphertextTooShort => "CiphertextTooShort",
+            DecryptionError::AuthenticationFailed => "AuthenticationFailed",
+            DecryptionError::OutputBufferTooSmall => "OutputBufferTooSmall",
+            DecryptionError::InvalidTagSize => "InvalidTagSize",
+            _ => "Unknown",
+        },
+    }
+}
+
+#[pyclass(module = "umbral")]
+pub struct DecryptionResult {
+    plaintext: Option<PyObject>,
+    failure: Option<&'static str>,
+    message: Option<String>,
+}
+
+#[pymethods]
+impl DecryptionResult {
+    #[getter]
+    pub fn ok(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    #[getter]
+    pub fn plaintext(&self) -> Option<PyObject> {
+        self.plaintext.clone()
+    }
+
+    #[getter]
+    pub fn failure(&self) -> Option<&'static str> {
+        self.failure
+    }
+
+    #[getter]
+    pub fn message(&self) -> Option<String> {
+        self.message.clone()
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for DecryptionResult {
+    fn __bool__(&self) -> bool {
+        self.ok()
+    }
+
+    fn __repr__(&self) -> String {
+        match self.failure {
+            None => "DecryptionResult(ok)".to_string(),
+            Some(failure) => format!("DecryptionResult(failure={})", failure),
+        }
+    }
+}
+
+#[pyfunction]
+pub fn try_decrypt_reencrypted(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    ciphertext: &[u8],
+) -> PyResult<DecryptionResult> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    let result = match umbral_pre::decrypt_reencrypted(
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+        ciphertext,
+    ) {
+        Ok(plaintext) => DecryptionResult {
+            plaintext: Some(PyBytes::new(py, &plaintext).into()),
+            failure: None,
+            message: None,
+        },
+        Err(err) => DecryptionResult {
+            plaintext: None,
+            failure: Some(reencryption_error_code(&err)),
+            message: Some(format!("{}", err)),
+        },
+    };
+    Ok(result)
+}
+
+#[pyclass(module = "umbral")]
+#[derive(PartialEq, Clone)]
+pub struct CombinationCoefficient {
+    backend: umbral_pre::CombinationCoefficient,
+}
+
+impl AsBackend<umbral_pre::CombinationCoefficient> for CombinationCoefficient {
+    fn as_backend(&self) -> &umbral_pre::CombinationCoefficient {
+        &self.backend
+    }
+}
+
+impl FromBackend<umbral_pre::CombinationCoefficient> for CombinationCoefficient {
+    fn from_backend(backend: umbral_pre::CombinationCoefficient) -> Self {
+        Self { backend }
+    }
+}
+
+#[pymethods]
+impl CombinationCoefficient {
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        from_bytes(data)
+    }
+
+    #[staticmethod]
+    pub fn serialized_size() -> usize {
+        umbral_pre::CombinationCoefficient::serialized_size()
+    }
+
+    fn __bytes__(&self) -> PyResult<PyObject> {
+        to_bytes(self)
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for CombinationCoefficient {
+    fn __richcmp__(&self, other: PyRef<CombinationCoefficient>, op: CompareOp) -> PyResult<bool> {
+        richcmp(self, other, op)
+    }
+
+    fn __hash__(&self) -> PyResult<isize> {
+        hash(self)
+    }
+
+    fn __str__(&self) -> PyResult<String> {
+        Ok(format!("{}", self.backend))
+    }
+}
+
+#[pyclass(module = "umbral")]
+#[derive(PartialEq, Clone)]
+pub struct CombinedCapsuleFrags {
+    backend: umbral_pre::CombinedCapsuleFrags,
+}
+
+impl AsBackend<umbral_pre::CombinedCapsuleFrags> for CombinedCapsuleFrags {
+    fn as_backend(&self) -> &umbral_pre::CombinedCapsuleFrags {
+        &self.backend
+    }
+}
+
+impl FromBackend<umbral_pre::CombinedCapsuleFrags> for CombinedCapsuleFrags {
+    fn from_backend(backend: umbral_pre::CombinedCapsuleFrags) -> Self {
+        Self { backend }
+    }
+}
+
+#[pymethods]
+impl CombinedCapsuleFrags {
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        from_bytes(data)
+    }
+
+    #[staticmethod]
+    pub fn serialized_size() -> usize {
+        umbral_pre::CombinedCapsuleFrags::serialized_size()
+    }
+
+    fn __bytes__(&self) -> PyResult<PyObject> {
+        to_bytes(self)
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for CombinedCapsuleFrags {
+    fn __richcmp__(&self, other: PyRef<CombinedCapsuleFrags>, op: CompareOp) -> PyResult<bool> {
+        richcmp(self, other, op)
+    }
+
+    fn __hash__(&self) -> PyResult<isize> {
+        hash(self)
+    }
+
+    fn __str__(&self) -> PyResult<String> {
+        Ok(format!("{}", self.backend))
+    }
+}
+
+#[pyfunction]
+pub fn combination_coefficients(
+    receiving_sk: &SecretKey,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+) -> PyResult<Vec<CombinationCoefficient>> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    umbral_pre::combination_coefficients(receiving_sk.backend()?, &backend_cfrags)
+        .map(|coefficients| {
+            coefficients
+                .iter()
+                .cloned()
+                .map(|backend| CombinationCoefficient { backend })
+                .collect()
+        })
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn combine_cfrags(
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    coefficients: Vec<CombinationCoefficient>,
+) -> PyResult<CombinedCapsuleFrags> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    let backend_coefficients: Vec<umbral_pre::CombinationCoefficient> = coefficients
+        .iter()
+        .map(|coefficient| coefficient.backend)
+        .collect();
+    umbral_pre::combine_cfrags(&backend_cfrags, &backend_coefficients)
+        .map(|backend| CombinedCapsuleFrags { backend })
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyfunction]
+pub fn decrypt_combined(
+    py: Python,
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: Vec<VerifiedCapsuleFrag>,
+    combined_cfrags: &CombinedCapsuleFrags,
+    ciphertext: &[u8],
+) -> PyResult<PyObject> {
+    let backend_cfrags: Vec<umbral_pre::VerifiedCapsuleFrag> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.backend)
+        .collect();
+    umbral_pre::decrypt_combined(
+        receiving_sk.backend()?,
+        &delegating_pk.backend,
+        &capsule.backend,
+        &backend_cfrags,
+        &combined_cfrags.backend,
+        ciphertext,
+    )
+    .map(|plaintext| PyBytes::new(py, &plaintext).into())
+    .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
+#[pyclass(module = "umbral")]
+pub struct SecurityParameters {
+    #[pyo3(get)]
+    curve: &'static str,
+    #[pyo3(get)]
+    curve_order_bits: usize,
+    #[pyo3(get)]
+    hash: &'static str,
+    #[pyo3(get)]
+    hash_output_bits: usize,
+    #[pyo3(get)]
+    aead: &'static str,
+    #[pyo3(get)]
+    aead_key_bits: usize,
+    #[pyo3(get)]
+    kdf: &'static str,
+    #[pyo3(get)]
+    security_bits: usize,
+}
+
+#[pyproto]
+impl PyObjectProtocol for SecurityParameters {
+    fn __repr__(&self) -> String {
+        format!(
+            "SecurityParameters(curve={}, hash={}, aead={}, kdf={}, security_bits={})",
+            self.curve, self.hash, self.aead, self.kdf, self.security_bits
+        )
+    }
+}
+
+#[pyfunction]
+pub fn backend_name() -> &'static str {
+    umbral_pre::backend_name()
+}
+
+#[pyfunction]
+pub fn features() -> HashSet<&'static str> {
+    umbral_pre::features().into_iter().collect()
+}
+
+fn operation_counts(counts: umbral_pre::OperationCounts) -> HashMap<&'static str, usize> {
+    [("total", counts.total), ("failed", counts.failed)]
+        .iter()
+        .cloned()
+        .collect()
+}
+
+#[pyfunction]
+pub fn metrics() -> HashMap<&'static str, HashMap<&'static str, usize>> {
+    let metrics = umbral_pre::metrics();
+    [
+        ("encrypt", metrics.encrypt),
+        ("reencrypt", metrics.reencrypt),
+        ("decrypt", metrics.decrypt),
+        ("verify", metrics.verify),
+    ]
+    .iter()
+    .map(|(name, counts)| (*name, operation_counts(*counts)))
+    .collect()
+}
+
+#[pyfunction]
+pub fn reset_metrics() {
+    umbral_pre::reset_metrics()
+}
+
+#[pyfunction]
+pub fn security_parameters() -> SecurityParameters {
+    let params = umbral_pre::security_parameters();
+    SecurityParameters {
+        curve: params.curve,
+        curve_order_bits: params.curve_order_bits,
+        hash: params.hash,
+        hash_output_bits: params.hash_output_bits,
+        aead: params.aead,
+        aead_key_bits: params.aead_key_bits,
+        kdf: params.kdf,
+        security_bits: params.security_bits,
+    }
+}
+
+#[pyfunction]
+pub fn hkdf(
+    py: Python,
+    ikm: &[u8],
+    salt: Option<&[u8]>,
+    info: Option<&[u8]>,
+    length: usize,
+) -> PyResult<PyObject> {
+    umbral_pre::hkdf(ikm, salt, info, length)
+        .map(|okm| PyBytes::new(py, okm.as_secret()).into())
+        .map_err(|err| PyValueError::new_err(format!("{}", err)))
+}
+
 /// A Python module implemented in Rust.
 #[pymodule]
 fn _umbral(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<SecretKey>()?;
     m.add_class::<SecretKeyFactory>()?;
+    m.add_class::<SecretBytes>()?;
+    m.add_class::<Keystore>()?;
+    m.add_class::<KeyRecord>()?;
     m.add_class::<PublicKey>()?;
     m.add_class::<Signer>()?;
     m.add_class::<Signature>()?;
@@ -748,11 +2079,57 @@ fn _umbral(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<VerifiedKeyFrag>()?;
     m.add_class::<CapsuleFrag>()?;
     m.add_class::<VerifiedCapsuleFrag>()?;
+    m.add_class::<CapsuleFragVerifier>()?;
+    m.add_class::<DecryptionResult>()?;
+    m.add_class::<CombinationCoefficient>()?;
+    m.add_class::<CombinedCapsuleFrags>()?;
+    m.add_class::<SecurityParameters>()?;
     m.add("VerificationError", py.get_type::<VerificationError>())?;
+    m.add(
+        "UnsupportedVersionError",
+        py.get_type::<UnsupportedVersionError>(),
+    )?;
     m.add_function(wrap_pyfunction!(encrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(encrypt_detached, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_original_detached, m)?)?;
     m.add_function(wrap_pyfunction!(decrypt_original, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_original_into, m)?)?;
+    m.add_function(wrap_pyfunction!(plaintext_size, m)?)?;
+    m.add_function(wrap_pyfunction!(is_owner, m)?)?;
+    m.add_function(wrap_pyfunction!(self_encrypt_decrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(same_capsule, m)?)?;
     m.add_function(wrap_pyfunction!(generate_kfrags, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_deterministic_kfrags, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_same_message, m)?)?;
+    m.add_function(wrap_pyfunction!(check_kfrags_consistency, m)?)?;
+    m.add_function(wrap_pyfunction!(delegations_equal, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_grant, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_cfrag_against_commitment, m)?)?;
     m.add_function(wrap_pyfunction!(reencrypt, m)?)?;
+    m.add_function(wrap_pyfunction!(reencrypt_into, m)?)?;
+    m.add_function(wrap_pyfunction!(reencrypt_multi, m)?)?;
     m.add_function(wrap_pyfunction!(decrypt_reencrypted, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_reencrypted_into, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_reencrypted_detached, m)?)?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_function(wrap_pyfunction!(derive_symmetric_key, m)?)?;
+    m.add_function(wrap_pyfunction!(open_symmetric_key, m)?)?;
+    m.add_function(wrap_pyfunction!(open_reencrypted_symmetric_key, m)?)?;
+    m.add_function(wrap_pyfunction!(serialize_cfrags_compact, m)?)?;
+    m.add_function(wrap_pyfunction!(deserialize_cfrags_compact, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_cfrags, m)?)?;
+    m.add_function(wrap_pyfunction!(encrypt_record, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_original_record, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_reencrypted_record, m)?)?;
+    m.add_function(wrap_pyfunction!(try_decrypt_reencrypted, m)?)?;
+    m.add_function(wrap_pyfunction!(combination_coefficients, m)?)?;
+    m.add_function(wrap_pyfunction!(combine_cfrags, m)?)?;
+    m.add_function(wrap_pyfunction!(decrypt_combined, m)?)?;
+    m.add_function(wrap_pyfunction!(security_parameters, m)?)?;
+    m.add_function(wrap_pyfunction!(backend_name, m)?)?;
+    m.add_function(wrap_pyfunction!(features, m)?)?;
+    m.add_function(wrap_pyfunction!(metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(hkdf, m)?)?;
     Ok(())
 }
@@ -1,6 +1,9 @@
 from ._umbral import (
     SecretKey,
     SecretKeyFactory,
+    SecretBytes,
+    Keystore,
+    KeyRecord,
     PublicKey,
     Signer,
     Signature,
@@ -9,10 +12,58 @@ from ._umbral import (
     VerifiedKeyFrag,
     CapsuleFrag,
     VerifiedCapsuleFrag,
+    CapsuleFragVerifier,
+    DecryptionResult,
+    CombinationCoefficient,
+    CombinedCapsuleFrags,
+    SecurityParameters,
     VerificationError,
+    UnsupportedVersionError,
     encrypt,
+    encrypt_detached,
+    decrypt_original_detached,
     decrypt_original,
+    decrypt_original_into,
+    plaintext_size,
+    is_owner,
+    self_encrypt_decrypt,
+    same_capsule,
     decrypt_reencrypted,
+    decrypt_reencrypted_into,
+    decrypt_reencrypted_detached,
+    open,
+    derive_symmetric_key,
+    open_symmetric_key,
+    open_reencrypted_symmetric_key,
+    serialize_cfrags_compact,
+    deserialize_cfrags_compact,
+    parse_cfrags,
+    encrypt_record,
+    decrypt_original_record,
+    decrypt_reencrypted_record,
+    try_decrypt_reencrypted,
+    combination_coefficients,
+    combine_cfrags,
+    decrypt_combined,
+    security_parameters,
+    backend_name,
+    features,
+    metrics,
+    reset_metrics,
+    hkdf,
     generate_kfrags,
+    generate_deterministic_kfrags,
+    verify_same_message,
+    check_kfrags_consistency,
+    delegations_equal,
+    validate_grant,
+    verify_cfrag_against_commitment,
     reencrypt,
+    reencrypt_into,
+    reencrypt_multi,
     )
+
+from .jsonl import write_jsonl, read_jsonl
+from .interop import make_interop_fixture, check_interop_fixture
+from .reliability import recommend_request_count
+from .streaming import verify_cfrags
@@ -1,4 +1,4 @@
-from typing import Optional, Tuple, List, Sequence
+from typing import Optional, Tuple, List, Sequence, Dict, Set, Union, Iterable, Iterator, IO, Callable
 
 
 class SecretKey:
@@ -13,6 +13,9 @@ class SecretKey:
     def to_secret_bytes(self) -> bytes:
         ...
 
+    def zeroize(self) -> None:
+        ...
+
     @staticmethod
     def from_bytes() -> SecretKey:
         ...
@@ -36,15 +39,25 @@ class SecretKeyFactory:
     def from_secure_randomness(seed: bytes) -> SecretKeyFactory:
         ...
 
+    @staticmethod
+    def derivation_version() -> int:
+        ...
+
     def make_key(self, label: bytes) -> SecretKey:
         ...
 
+    def receiving_keypair_by_label(self, label: bytes) -> Tuple[SecretKey, PublicKey]:
+        ...
+
     def make_factory(self, label: bytes) -> SecretKeyFactory:
         ...
 
     def to_secret_bytes(self) -> bytes:
         ...
 
+    def to_secret_bytes_wrapper(self) -> SecretBytes:
+        ...
+
     @staticmethod
     def from_bytes() -> SecretKeyFactory:
         ...
@@ -54,6 +67,70 @@ class SecretKeyFactory:
         ...
 
 
+class SecretBytes:
+
+    def expose_secret(self) -> bytes:
+        ...
+
+
+class Keystore:
+
+    def __init__(self):
+        ...
+
+    def add(self, name: str, secret_key: SecretKey) -> None:
+        ...
+
+    def get(self, name: str) -> SecretKey:
+        ...
+
+    def remove(self, name: str) -> None:
+        ...
+
+    def names(self) -> List[str]:
+        ...
+
+    def encrypt(self, passphrase: bytes) -> bytes:
+        ...
+
+    @staticmethod
+    def decrypt(data: bytes, passphrase: bytes) -> Keystore:
+        ...
+
+    def __len__(self) -> int:
+        ...
+
+
+class KeyRecord:
+
+    def __init__(self, public_key: PublicKey, label: str, created_at: Optional[int] = None):
+        ...
+
+    @staticmethod
+    def from_secret_key(
+            secret_key: SecretKey,
+            label: str,
+            passphrase: bytes,
+            created_at: Optional[int] = None,
+            ) -> KeyRecord:
+        ...
+
+    public_key: PublicKey
+    label: str
+    created_at: int
+    has_secret_key: bool
+
+    def secret_key(self, passphrase: bytes) -> Optional[SecretKey]:
+        ...
+
+    @staticmethod
+    def from_bytes(data: bytes) -> KeyRecord:
+        ...
+
+    def __bytes__(self) -> bytes:
+        ...
+
+
 class PublicKey:
 
     @staticmethod
@@ -76,6 +153,12 @@ class Signer:
     def verifying_key() -> PublicKey:
         ...
 
+    def verifying_key_equals(verifying_pk: PublicKey) -> bool:
+        ...
+
+    def zeroize(self) -> None:
+        ...
+
 
 class Signature:
 
@@ -91,6 +174,15 @@ class Signature:
         ...
 
 
+def verify_same_message(
+        signatures: Sequence[Tuple[Signature, PublicKey]],
+        message: bytes,
+        progress: Optional[Callable[[int, int], None]] = None,
+        progress_every: Optional[int] = None,
+        ) -> List[bool]:
+    ...
+
+
 class Capsule:
 
     @staticmethod
@@ -102,10 +194,49 @@ def encrypt(delegating_pk: PublicKey, plaintext: bytes) -> Tuple[Capsule, bytes]
     ...
 
 
+def encrypt_detached(delegating_pk: PublicKey, plaintext: bytes) -> Tuple[Capsule, bytes, bytes]:
+    ...
+
+
+def decrypt_original_detached(
+        delegating_sk: SecretKey,
+        capsule: Capsule,
+        ciphertext_body: bytes,
+        tag: bytes,
+        ) -> bytes:
+    ...
+
+
 def decrypt_original(delegating_sk: SecretKey, capsule: Capsule, ciphertext: bytes) -> bytes:
     ...
 
 
+def decrypt_original_into(
+        delegating_sk: SecretKey,
+        capsule: Capsule,
+        ciphertext: bytes,
+        out: bytearray,
+        offset: Optional[int] = None,
+        ) -> int:
+    ...
+
+
+def plaintext_size(ciphertext_size: int) -> Optional[int]:
+    ...
+
+
+def is_owner(delegating_sk: SecretKey, capsule: Capsule, ciphertext: bytes) -> bool:
+    ...
+
+
+def self_encrypt_decrypt(delegating_sk: SecretKey) -> bool:
+    ...
+
+
+def same_capsule(capsule_a: Capsule, capsule_b: Capsule) -> bool:
+    ...
+
+
 class KeyFrag:
 
     def verify(
@@ -116,6 +247,9 @@ class KeyFrag:
             ) -> VerifiedKeyFrag:
         ...
 
+    def commitment(self) -> bytes:
+        ...
+
     @staticmethod
     def from_bytes() -> KeyFrag:
         ...
@@ -130,6 +264,9 @@ class VerifiedKeyFrag:
     def from_verified_bytes(data: bytes) -> VerifiedKeyFrag:
         ...
 
+    def commitment(self) -> bytes:
+        ...
+
     @staticmethod
     def serialized_size() -> int:
         ...
@@ -143,6 +280,22 @@ def generate_kfrags(
         shares: int,
         sign_delegating_key: bool,
         sign_receiving_key: bool,
+        id_seed: Optional[bytes] = None,
+        id_labels: Optional[Sequence[bytes]] = None,
+        node_labels: Optional[Sequence[str]] = None,
+        ) -> Union[List[VerifiedKeyFrag], Dict[str, VerifiedKeyFrag]]:
+    ...
+
+
+def generate_deterministic_kfrags(
+        delegating_sk: SecretKey,
+        receiving_pk: PublicKey,
+        signer: Signer,
+        threshold: int,
+        delegation_seed: bytes,
+        labels: Sequence[bytes],
+        sign_delegating_key: bool,
+        sign_receiving_key: bool,
         ) -> List[VerifiedKeyFrag]:
     ...
 
@@ -158,6 +311,17 @@ class CapsuleFrag:
             ) -> VerifiedCapsuleFrag:
         ...
 
+    def verify_signature_only(
+            self,
+            verifying_pk: PublicKey,
+            delegating_pk: PublicKey,
+            receiving_pk: PublicKey,
+            ) -> bool:
+        ...
+
+    def is_for_receiver(self, receiving_sk: SecretKey) -> bool:
+        ...
+
     @staticmethod
     def from_bytes() -> CapsuleFrag:
         ...
@@ -172,15 +336,80 @@ class VerifiedCapsuleFrag:
     def from_verified_bytes(data: bytes) -> VerifiedCapsuleFrag:
         ...
 
+    def is_for_receiver(self, receiving_sk: SecretKey) -> bool:
+        ...
+
     @staticmethod
     def serialized_size() -> int:
         ...
 
 
+class CapsuleFragVerifier:
+
+    def __init__(
+            capsule: Capsule,
+            verifying_pk: PublicKey,
+            delegating_pk: PublicKey,
+            receiving_pk: PublicKey,
+            ):
+        ...
+
+    def verify(cfrag: CapsuleFrag) -> VerifiedCapsuleFrag:
+        ...
+
+
+def check_kfrags_consistency(kfrags: Sequence[VerifiedKeyFrag]) -> None:
+    ...
+
+
+def delegations_equal(
+        kfrags_a: Sequence[VerifiedKeyFrag],
+        kfrags_b: Sequence[VerifiedKeyFrag],
+        ) -> bool:
+    ...
+
+
+def validate_grant(
+        delegating_sk: SecretKey,
+        receiving_key: Union[SecretKey, PublicKey],
+        signer: Signer,
+        kfrags: Sequence[VerifiedKeyFrag],
+        threshold: int,
+        ) -> bool:
+    ...
+
+
+def verify_cfrag_against_commitment(
+        cfrag: CapsuleFrag,
+        commitment: bytes,
+        capsule: Capsule,
+        ) -> bool:
+    ...
+
+
 def reencrypt(capsule: Capsule, kfrag: VerifiedKeyFrag) -> VerifiedCapsuleFrag:
     ...
 
 
+def reencrypt_into(
+        capsule: Capsule,
+        kfrag: VerifiedKeyFrag,
+        out: bytearray,
+        offset: Optional[int] = None,
+        ) -> None:
+    ...
+
+
+def reencrypt_multi(
+        capsule: Capsule,
+        kfrags: Sequence[Union[KeyFrag, VerifiedKeyFrag]],
+        verifying_pk: Optional[PublicKey] = None,
+        delegating_pk: Optional[PublicKey] = None,
+        receiving_pk: Optional[PublicKey] = None,
+        ) -> List[Union[VerifiedCapsuleFrag, VerificationError]]:
+    ...
+
+
 def decrypt_reencrypted(
         receiving_sk: SecretKey,
         delegating_pk: PublicKey,
@@ -189,3 +418,263 @@ def decrypt_reencrypted(
         ciphertext: bytes,
         ) -> Optional[bytes]:
     ...
+
+
+def decrypt_reencrypted_detached(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ciphertext_body: bytes,
+        tag: bytes,
+        ) -> bytes:
+    ...
+
+
+def decrypt_reencrypted_into(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ciphertext: bytes,
+        out: bytearray,
+        offset: Optional[int] = None,
+        ) -> int:
+    ...
+
+
+def open(
+        decrypting_sk: SecretKey,
+        capsule: Capsule,
+        ciphertext: bytes,
+        cfrags: Optional[Sequence[Union[CapsuleFrag, VerifiedCapsuleFrag]]] = None,
+        delegating_pk: Optional[PublicKey] = None,
+        verifying_pk: Optional[PublicKey] = None,
+        ) -> bytes:
+    ...
+
+
+def derive_symmetric_key(delegating_pk: PublicKey) -> Tuple[Capsule, bytes]:
+    ...
+
+
+def open_symmetric_key(delegating_sk: SecretKey, capsule: Capsule) -> bytes:
+    ...
+
+
+def open_reencrypted_symmetric_key(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ) -> bytes:
+    ...
+
+
+class VerificationError(Exception):
+    ...
+
+
+class UnsupportedVersionError(ValueError):
+    ...
+
+
+def serialize_cfrags_compact(cfrags: Sequence[Union[CapsuleFrag, VerifiedCapsuleFrag]]) -> bytes:
+    ...
+
+
+def deserialize_cfrags_compact(data: bytes) -> List[CapsuleFrag]:
+    ...
+
+
+def parse_cfrags(data: bytes) -> List[CapsuleFrag]:
+    ...
+
+
+def encrypt_record(
+        delegating_pk: PublicKey,
+        plaintext: bytes,
+        record_id: bytes,
+        schema_version: int,
+        ) -> Tuple[Capsule, bytes]:
+    ...
+
+
+def decrypt_original_record(
+        delegating_sk: SecretKey,
+        capsule: Capsule,
+        ciphertext: bytes,
+        record_id: bytes,
+        schema_version: int,
+        ) -> bytes:
+    ...
+
+
+def decrypt_reencrypted_record(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ciphertext: bytes,
+        record_id: bytes,
+        schema_version: int,
+        ) -> bytes:
+    ...
+
+
+class DecryptionResult:
+
+    ok: bool
+
+    plaintext: Optional[bytes]
+
+    failure: Optional[str]
+
+    message: Optional[str]
+
+    def __bool__(self) -> bool:
+        ...
+
+
+def try_decrypt_reencrypted(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ciphertext: bytes,
+        ) -> DecryptionResult:
+    ...
+
+
+class CombinationCoefficient:
+
+    @staticmethod
+    def from_bytes() -> CombinationCoefficient:
+        ...
+
+    @staticmethod
+    def serialized_size() -> int:
+        ...
+
+
+class CombinedCapsuleFrags:
+
+    @staticmethod
+    def from_bytes() -> CombinedCapsuleFrags:
+        ...
+
+    @staticmethod
+    def serialized_size() -> int:
+        ...
+
+
+def combination_coefficients(
+        receiving_sk: SecretKey,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        ) -> List[CombinationCoefficient]:
+    ...
+
+
+def combine_cfrags(
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        coefficients: Sequence[CombinationCoefficient],
+        ) -> CombinedCapsuleFrags:
+    ...
+
+
+def decrypt_combined(
+        receiving_sk: SecretKey,
+        delegating_pk: PublicKey,
+        capsule: Capsule,
+        cfrags: Sequence[VerifiedCapsuleFrag],
+        combined_cfrags: CombinedCapsuleFrags,
+        ciphertext: bytes,
+        ) -> bytes:
+    ...
+
+
+class SecurityParameters:
+
+    curve: str
+
+    curve_order_bits: int
+
+    hash: str
+
+    hash_output_bits: int
+
+    aead: str
+
+    aead_key_bits: int
+
+    kdf: str
+
+    security_bits: int
+
+
+def security_parameters() -> SecurityParameters:
+    ...
+
+
+def backend_name() -> str:
+    ...
+
+
+def features() -> Set[str]:
+    ...
+
+
+def metrics() -> Dict[str, Dict[str, int]]:
+    ...
+
+
+def reset_metrics() -> None:
+    ...
+
+
+def hkdf(ikm: bytes, salt: Optional[bytes], info: Optional[bytes], length: int) -> bytes:
+    ...
+
+
+ExportableObject = Union[
+    PublicKey, Signature, Capsule, KeyFrag, VerifiedKeyFrag, CapsuleFrag, VerifiedCapsuleFrag,
+    CombinationCoefficient, CombinedCapsuleFrags]
+
+
+def write_jsonl(objects: Iterable[ExportableObject], file: IO[str]) -> int:
+    ...
+
+
+def read_jsonl(
+        file: Iterable[str],
+        ) -> Iterator[Union[PublicKey, Signature, Capsule, KeyFrag, CapsuleFrag,
+                            CombinationCoefficient, CombinedCapsuleFrags]]:
+    ...
+
+
+def make_interop_fixture(seed: bytes) -> str:
+    ...
+
+
+def check_interop_fixture(fixture_json: str) -> None:
+    ...
+
+
+def recommend_request_count(
+        threshold: int,
+        total: int,
+        node_success_prob: float,
+        target_reliability: float,
+        ) -> int:
+    ...
+
+
+def verify_cfrags(
+        cfrags: Iterable[Union[CapsuleFrag, bytes]],
+        capsule: Capsule,
+        verifying_pk: PublicKey,
+        delegating_pk: PublicKey,
+        receiving_pk: PublicKey,
+        skip_invalid: bool = False,
+        ) -> Iterator[VerifiedCapsuleFrag]:
+    ...
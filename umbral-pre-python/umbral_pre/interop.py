@@ -0,0 +1,117 @@
+import base64
+import hashlib
+import json
+
+from ._umbral import (
+    SecretKey,
+    SecretKeyFactory,
+    PublicKey,
+    Signer,
+    Capsule,
+    KeyFrag,
+    CapsuleFrag,
+    encrypt,
+    decrypt_original,
+    generate_kfrags,
+    reencrypt,
+    decrypt_reencrypted,
+    )
+
+
+FIXTURE_VERSION = 1
+
+_THRESHOLD = 2
+_SHARES = 3
+_PLAINTEXT = b"peace at dawn"
+
+
+def _encode(data):
+    return base64.b64encode(data).decode("ascii")
+
+
+def _decode(value, name):
+    try:
+        return base64.b64decode(value, validate=True)
+    except (TypeError, ValueError) as exc:
+        raise ValueError("Invalid fixture field {}: {}".format(name, exc)) from exc
+
+
+def make_interop_fixture(seed):
+    # Only the keys and the kfrag IDs can be derived from the seed,
+    # the rest of the randomness comes from the OS RNG.
+    factory_seed = hashlib.shake_256(b"INTEROP_FIXTURE" + seed).digest(SecretKeyFactory.seed_size())
+    factory = SecretKeyFactory.from_secure_randomness(factory_seed)
+
+    delegating_sk = factory.make_key(b"delegating")
+    receiving_sk = factory.make_key(b"receiving")
+    signing_sk = factory.make_key(b"signing")
+    signer = Signer(signing_sk)
+
+    capsule, ciphertext = encrypt(delegating_sk.public_key(), _PLAINTEXT)
+    kfrags = generate_kfrags(
+        delegating_sk, receiving_sk.public_key(), signer, _THRESHOLD, _SHARES, True, True,
+        id_seed=seed, id_labels=[b"kfrag-%d" % i for i in range(_SHARES)])
+    cfrags = [reencrypt(capsule, kfrag) for kfrag in kfrags]
+
+    fixture = dict(
+        version=FIXTURE_VERSION,
+        threshold=_THRESHOLD,
+        delegating_sk=_encode(delegating_sk.to_secret_bytes()),
+        delegating_pk=_encode(bytes(delegating_sk.public_key())),
+        receiving_sk=_encode(receiving_sk.to_secret_bytes()),
+        receiving_pk=_encode(bytes(receiving_sk.public_key())),
+        signing_sk=_encode(signing_sk.to_secret_bytes()),
+        verifying_pk=_encode(bytes(signer.verifying_key())),
+        plaintext=_encode(_PLAINTEXT),
+        capsule=_encode(bytes(capsule)),
+        ciphertext=_encode(ciphertext),
+        kfrags=[_encode(bytes(kfrag)) for kfrag in kfrags],
+        cfrags=[_encode(bytes(cfrag)) for cfrag in cfrags],
+        )
+    return json.dumps(fixture, indent=2)
+
+
+def check_interop_fixture(fixture_json):
+    fixture = json.loads(fixture_json)
+    if fixture.get("version") != FIXTURE_VERSION:
+        raise ValueError("Unsupported fixture version: {}".format(fixture.get("version")))
+
+    delegating_sk = SecretKey.from_bytes(_decode(fixture.get("delegating_sk"), "delegating_sk"))
+    receiving_sk = SecretKey.from_bytes(_decode(fixture.get("receiving_sk"), "receiving_sk"))
+    signing_sk = SecretKey.from_bytes(_decode(fixture.get("signing_sk"), "signing_sk"))
+
+    delegating_pk = PublicKey.from_bytes(_decode(fixture.get("delegating_pk"), "delegating_pk"))
+    receiving_pk = PublicKey.from_bytes(_decode(fixture.get("receiving_pk"), "receiving_pk"))
+    verifying_pk = PublicKey.from_bytes(_decode(fixture.get("verifying_pk"), "verifying_pk"))
+
+    if (delegating_sk.public_key() != delegating_pk
+            or receiving_sk.public_key() != receiving_pk
+            or signing_sk.public_key() != verifying_pk):
+        raise ValueError("The public keys do not correspond to the secret keys")
+
+    plaintext = _decode(fixture.get("plaintext"), "plaintext")
+    capsule = Capsule.from_bytes(_decode(fixture.get("capsule"), "capsule"))
+    ciphertext = _decode(fixture.get("ciphertext"), "ciphertext")
+
+    if decrypt_original(delegating_sk, capsule, ciphertext) != plaintext:
+        raise ValueError("The original decryption result does not match the plaintext")
+
+    kfrags = [
+        KeyFrag.from_bytes(_decode(kfrag, "kfrags"))
+        .verify(verifying_pk, delegating_pk, receiving_pk)
+        for kfrag in fixture["kfrags"]]
+    cfrags = [
+        CapsuleFrag.from_bytes(_decode(cfrag, "cfrags"))
+        .verify(capsule, verifying_pk, delegating_pk, receiving_pk)
+        for cfrag in fixture["cfrags"]]
+
+    threshold = fixture["threshold"]
+    if decrypt_reencrypted(
+            receiving_sk, delegating_pk, capsule, cfrags[:threshold], ciphertext) != plaintext:
+        raise ValueError("The reencrypted decryption result does not match the plaintext")
+
+    # The kfrags must be usable with the local implementation as well
+    new_cfrags = [reencrypt(capsule, kfrag) for kfrag in kfrags[:threshold]]
+    if decrypt_reencrypted(
+            receiving_sk, delegating_pk, capsule, new_cfrags, ciphertext) != plaintext:
+        raise ValueError("Decryption with the locally reencrypted fragments failed")
@@ -0,0 +1,73 @@
+import base64
+import json
+
+from ._umbral import (
+    PublicKey,
+    Signature,
+    Capsule,
+    KeyFrag,
+    VerifiedKeyFrag,
+    CapsuleFrag,
+    VerifiedCapsuleFrag,
+    CombinationCoefficient,
+    CombinedCapsuleFrags,
+    )
+
+
+# Verified objects are exported as their unverified counterparts,
+# since the verification status cannot be trusted after a roundtrip through storage.
+_EXPORTED_TYPES = {
+    PublicKey: "PublicKey",
+    Signature: "Signature",
+    Capsule: "Capsule",
+    KeyFrag: "KeyFrag",
+    VerifiedKeyFrag: "KeyFrag",
+    CapsuleFrag: "CapsuleFrag",
+    VerifiedCapsuleFrag: "CapsuleFrag",
+    CombinationCoefficient: "CombinationCoefficient",
+    CombinedCapsuleFrags: "CombinedCapsuleFrags",
+}
+
+_IMPORTED_TYPES = {
+    "PublicKey": PublicKey,
+    "Signature": Signature,
+    "Capsule": Capsule,
+    "KeyFrag": KeyFrag,
+    "CapsuleFrag": CapsuleFrag,
+    "CombinationCoefficient": CombinationCoefficient,
+    "CombinedCapsuleFrags": CombinedCapsuleFrags,
+}
+
+
+def write_jsonl(objects, file):
+    count = 0
+    for obj in objects:
+        type_name = _EXPORTED_TYPES.get(type(obj))
+        if type_name is None:
+            raise TypeError("Cannot export an object of type " + type(obj).__name__)
+        data = base64.b64encode(bytes(obj)).decode("ascii")
+        file.write(json.dumps(dict(type=type_name, data=data)) + "\n")
+        count += 1
+    return count
+
+
+def read_jsonl(file):
+    for line_number, line in enumerate(file, 1):
+        if not line.strip():
+            continue
+        try:
+            record = json.loads(line)
+            type_name = record["type"]
+            data = base64.b64decode(record["data"], validate=True)
+        except (ValueError, KeyError, TypeError) as exc:
+            raise ValueError("Invalid record on line {}: {}".format(line_number, exc)) from exc
+
+        cls = _IMPORTED_TYPES.get(type_name)
+        if cls is None:
+            raise ValueError("Unknown type on line {}: {}".format(line_number, type_name))
+
+        try:
+            obj = cls.from_bytes(data)
+        except ValueError as exc:
+            raise ValueError("Invalid record on line {}: {}".format(line_number, exc)) from exc
+        yield obj
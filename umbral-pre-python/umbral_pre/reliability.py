@@ -0,0 +1,29 @@
+def _check_probability(value, name):
+    if not 0 <= value <= 1:
+        raise ValueError("{} must be within [0, 1], got {}".format(name, value))
+
+
+def recommend_request_count(threshold, total, node_success_prob, target_reliability):
+    if threshold < 1:
+        raise ValueError("threshold must be positive, got {}".format(threshold))
+    if threshold > total:
+        raise ValueError(
+            "threshold ({}) cannot be greater than the total number of nodes ({})"
+            .format(threshold, total))
+    _check_probability(node_success_prob, "node_success_prob")
+    _check_probability(target_reliability, "target_reliability")
+
+    # `successes[k]` is the probability of exactly `k` out of `requested` nodes responding.
+    # Nodes are assumed to fail independently.
+    successes = [1.0]
+    for requested in range(1, total + 1):
+        successes = [
+            (successes[k] if k < requested else 0) * (1 - node_success_prob)
+            + (successes[k - 1] * node_success_prob if k > 0 else 0)
+            for k in range(requested + 1)]
+        if requested >= threshold and sum(successes[threshold:]) >= target_reliability:
+            return requested
+
+    raise ValueError(
+        "The target reliability {} cannot be reached with {} nodes"
+        .format(target_reliability, total))
@@ -0,0 +1,19 @@
+from ._umbral import (
+    CapsuleFrag,
+    CapsuleFragVerifier,
+    VerificationError,
+    )
+
+
+def verify_cfrags(cfrags, capsule, verifying_pk, delegating_pk, receiving_pk, skip_invalid=False):
+    verifier = CapsuleFragVerifier(capsule, verifying_pk, delegating_pk, receiving_pk)
+    for cfrag in cfrags:
+        try:
+            if not isinstance(cfrag, CapsuleFrag):
+                cfrag = CapsuleFrag.from_bytes(bytes(cfrag))
+            verified_cfrag = verifier.verify(cfrag)
+        except (ValueError, VerificationError):
+            if skip_invalid:
+                continue
+            raise
+        yield verified_cfrag
@@ -76,6 +76,11 @@ impl SecretKeyFactory {
         umbral_pre::SecretKeyFactory::seed_size()
     }
 
+    #[wasm_bindgen(js_name = derivationVersion)]
+    pub fn derivation_version() -> u32 {
+        umbral_pre::SecretKeyFactory::DERIVATION_VERSION
+    }
+
     #[wasm_bindgen(js_name = fromSecureRandomness)]
     pub fn from_secure_randomness(seed: &[u8]) -> Result<SecretKeyFactory, JsValue> {
         umbral_pre::SecretKeyFactory::from_secure_randomness(seed)
@@ -163,6 +168,11 @@ impl Signer {
         PublicKey(self.0.verifying_key())
     }
 
+    #[wasm_bindgen(js_name = verifyingKeyEquals)]
+    pub fn verifying_key_equals(&self, verifying_pk: &PublicKey) -> bool {
+        self.0.verifying_key_equals(&verifying_pk.0)
+    }
+
     #[allow(clippy::inherent_to_string)]
     #[wasm_bindgen(js_name = toString)]
     pub fn to_string(&self) -> String {
@@ -319,6 +329,35 @@ impl VerifiedCapsuleFrag {
     }
 }
 
+#[wasm_bindgen]
+pub struct CapsuleFragVerifier(umbral_pre::CapsuleFragVerifier);
+
+#[wasm_bindgen]
+impl CapsuleFragVerifier {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        capsule: &Capsule,
+        verifying_pk: &PublicKey,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+    ) -> Self {
+        Self(umbral_pre::CapsuleFragVerifier::new(
+            &capsule.0,
+            &verifying_pk.0,
+            &delegating_pk.0,
+            &receiving_pk.0,
+        ))
+    }
+
+    #[wasm_bindgen]
+    pub fn verify(&self, cfrag: &CapsuleFrag) -> Result<VerifiedCapsuleFrag, JsValue> {
+        self.0
+            .verify(&cfrag.0)
+            .map(VerifiedCapsuleFrag)
+            .map_err(map_js_err)
+    }
+}
+
 #[wasm_bindgen]
 pub struct CapsuleWithFrags {
     capsule: Capsule,
@@ -0,0 +1,82 @@
+//! Counts the heap allocations made by the main operations.
+//!
+//! Run with `cargo bench --bench allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use umbral_pre::{
+    decrypt_reencrypted, encrypt, generate_kfrags, reencrypt, RepresentableAsArray, SecretKey,
+    SerializableToArray, Signer, VerifiedCapsuleFrag,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ITERATIONS: usize = 1000;
+
+fn count_allocations<T>(name: &str, mut f: impl FnMut() -> T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        drop(f());
+    }
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    println!(
+        "{:<40} {:>8.2} allocations per call",
+        name,
+        (after - before) as f64 / ITERATIONS as f64
+    );
+}
+
+fn main() {
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = delegating_sk.public_key();
+
+    let signer = Signer::new(&SecretKey::random());
+
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = receiving_sk.public_key();
+
+    let plaintext = b"peace at dawn";
+    let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+
+    let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+    let verified_cfrags: Vec<_> = kfrags[0..2]
+        .iter()
+        .map(|vkfrag| reencrypt(&capsule, vkfrag))
+        .collect();
+
+    count_allocations("reencrypt", || reencrypt(&capsule, &kfrags[0]));
+
+    let mut out = vec![0u8; VerifiedCapsuleFrag::serialized_size()];
+    count_allocations("reencrypt + serialization into a buffer", || {
+        out.copy_from_slice(&reencrypt(&capsule, &kfrags[0]).to_array())
+    });
+
+    count_allocations("encrypt", || encrypt(&delegating_pk, plaintext));
+
+    count_allocations("decrypt_reencrypted", || {
+        decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags,
+            &ciphertext,
+        )
+    });
+}
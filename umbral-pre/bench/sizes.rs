@@ -0,0 +1,56 @@
+//! Compares the size of the compact serialization of multiple capsule fragments
+//! with the concatenation of the fragments serialized separately.
+//!
+//! Run with `cargo bench --bench sizes`.
+
+use umbral_pre::{
+    encrypt, generate_kfrags, reencrypt, serialize_cfrags_compact, RepresentableAsArray, SecretKey,
+    Signer, VerifiedCapsuleFrag,
+};
+
+fn main() {
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = delegating_sk.public_key();
+    let signer = Signer::new(&SecretKey::random());
+    let receiving_pk = SecretKey::random().public_key();
+
+    let (capsule, _ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+
+    println!(
+        "{:>8} {:>12} {:>12} {:>8}",
+        "cfrags", "naive", "compact", "saved"
+    );
+    for &shares in [1usize, 2, 5, 10, 20, 50].iter() {
+        let kfrags = generate_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            1,
+            shares,
+            true,
+            true,
+        );
+        let cfrags: Vec<_> = kfrags
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag).unverify())
+            .collect();
+
+        let naive = shares * VerifiedCapsuleFrag::serialized_size();
+        let compact = serialize_cfrags_compact(&cfrags).unwrap().len();
+        // With a single fragment the compact form is larger because of the version tag,
+        // so the difference can be negative.
+        let saved = 100. * (naive as f64 - compact as f64) / naive as f64;
+        println!(
+            "{:>8} {:>12} {:>12} {:>7.1}%{}",
+            shares,
+            naive,
+            compact,
+            saved,
+            if saved < 0. {
+                " (compact is larger)"
+            } else {
+                ""
+            }
+        );
+    }
+}
@@ -1,12 +1,18 @@
 //! Rust benchmarks are located in an external module, and cannot access private functions.
 //! This module re-exports some internals for the purposes of benchmarking.
+//! It also contains constructors of deterministic dummy objects
+//! for tests and benchmarks (available in the crate's own tests as well).
 //! Should not be used by regular users.
 
-use rand_core::OsRng;
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha2::{digest::Digest, Sha256};
 
 use crate::capsule::{Capsule, KeySeed, OpenReencryptedError};
 use crate::capsule_frag::{CapsuleFrag, VerifiedCapsuleFrag};
-use crate::keys::{PublicKey, SecretKey};
+use crate::dem::hkdf;
+use crate::key_frag::VerifiedKeyFrag;
+use crate::keys::{PublicKey, SecretKey, SecretKeyFactory, Signer};
+use crate::pre::{generate_deterministic_kfrags_with_rng, reencrypt_with_rng};
 use crate::secret_box::SecretBox;
 
 pub use crate::hashing::unsafe_hash_to_point;
@@ -35,3 +41,181 @@ pub fn capsule_open_reencrypted(
 pub fn get_cfrag(verified_cfrag: &VerifiedCapsuleFrag) -> &CapsuleFrag {
     &verified_cfrag.cfrag
 }
+
+/// A deterministic RNG producing the SHA-256 hashes of the seed and a block counter.
+///
+/// Only intended for making reproducible dummy objects; never use it for real keys.
+pub struct DummyRng {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 32],
+    position: usize,
+}
+
+impl DummyRng {
+    /// Creates an RNG from the given seed and a label separating the uses of the same seed.
+    pub fn new(seed: &[u8], label: &[u8]) -> Self {
+        let digest = Sha256::new()
+            .chain((label.len() as u32).to_be_bytes())
+            .chain(label)
+            .chain(seed)
+            .finalize();
+        let mut rng_seed = [0u8; 32];
+        rng_seed.copy_from_slice(&digest);
+        Self {
+            seed: rng_seed,
+            counter: 0,
+            block: [0u8; 32],
+            position: 32,
+        }
+    }
+}
+
+impl RngCore for DummyRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.position == self.block.len() {
+                let digest = Sha256::new()
+                    .chain(self.seed)
+                    .chain(self.counter.to_be_bytes())
+                    .finalize();
+                self.block.copy_from_slice(&digest);
+                self.counter += 1;
+                self.position = 0;
+            }
+            *byte = self.block[self.position];
+            self.position += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+// A lie, but the dummy objects only need to be valid, not secure.
+impl CryptoRng for DummyRng {}
+
+/// Returns the delegating secret key, the receiving secret key, and the signer
+/// deterministically derived from `seed`.
+///
+/// These are the keys the dummy objects with the same seed were created with.
+pub fn dummy_keys(seed: &[u8]) -> (SecretKey, SecretKey, Signer) {
+    // Cannot fail since the requested length is small.
+    let factory_seed = hkdf(
+        seed,
+        None,
+        Some(b"DUMMY_KEYS"),
+        SecretKeyFactory::seed_size(),
+    )
+    .unwrap();
+    // Cannot fail since the sizes match.
+    let factory = SecretKeyFactory::from_secure_randomness(factory_seed.as_secret()).unwrap();
+    // Cannot fail since the labels are short.
+    let delegating_sk = factory.make_key(b"delegating").unwrap();
+    let receiving_sk = factory.make_key(b"receiving").unwrap();
+    let signer = Signer::new(&factory.make_key(b"signing").unwrap());
+    (delegating_sk, receiving_sk, signer)
+}
+
+/// Creates a valid capsule for the delegating key from [`dummy_keys`],
+/// deterministically derived from `seed`.
+pub fn dummy_capsule(seed: &[u8]) -> Capsule {
+    let (delegating_sk, _receiving_sk, _signer) = dummy_keys(seed);
+    let mut rng = DummyRng::new(seed, b"capsule");
+    let (capsule, _key_seed) = Capsule::from_public_key(&mut rng, &delegating_sk.public_key());
+    capsule
+}
+
+/// Creates a valid key fragment (of a delegation with the threshold 1) for the keys
+/// from [`dummy_keys`], deterministically derived from `seed`.
+///
+/// Both the delegating and the receiving keys are included in the signature.
+pub fn dummy_kfrag(seed: &[u8]) -> VerifiedKeyFrag {
+    let (delegating_sk, receiving_sk, signer) = dummy_keys(seed);
+    let mut rng = DummyRng::new(seed, b"kfrag");
+    let kfrags = generate_deterministic_kfrags_with_rng(
+        &mut rng,
+        &delegating_sk,
+        &receiving_sk.public_key(),
+        &signer,
+        1,
+        seed,
+        &[b"dummy"],
+        true,
+        true,
+    )
+    .unwrap();
+    kfrags[0].clone()
+}
+
+/// Creates a valid capsule fragment from [`dummy_capsule`] and [`dummy_kfrag`]
+/// with the same `seed`, deterministically derived from it.
+///
+/// Since the threshold is 1, the fragment is enough to decrypt
+/// ciphertexts encapsulated in the capsule.
+pub fn dummy_cfrag(seed: &[u8]) -> VerifiedCapsuleFrag {
+    let capsule = dummy_capsule(seed);
+    let kfrag = dummy_kfrag(seed);
+    let mut rng = DummyRng::new(seed, b"cfrag");
+    reencrypt_with_rng(&mut rng, &capsule, &kfrag)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{dummy_capsule, dummy_cfrag, dummy_keys, dummy_kfrag};
+    use crate::{CapsuleFrag, DeserializableFromArray, KeyFrag, SerializableToArray};
+
+    #[test]
+    fn test_dummy_objects() {
+        let seed = b"dummy seed";
+        let (delegating_sk, receiving_sk, signer) = dummy_keys(seed);
+        let delegating_pk = delegating_sk.public_key();
+        let receiving_pk = receiving_sk.public_key();
+        let verifying_pk = signer.verifying_key();
+
+        let capsule = dummy_capsule(seed);
+        let vkfrag = dummy_kfrag(seed);
+        let vcfrag = dummy_cfrag(seed);
+
+        // Deterministic
+        assert_eq!(dummy_capsule(seed), capsule);
+        assert_eq!(dummy_kfrag(seed), vkfrag);
+        assert_eq!(dummy_cfrag(seed), vcfrag);
+
+        // Different seeds produce different objects
+        let other_seed = b"other dummy seed";
+        assert_ne!(dummy_capsule(other_seed), capsule);
+        assert_ne!(dummy_kfrag(other_seed), vkfrag);
+        assert_ne!(dummy_cfrag(other_seed), vcfrag);
+
+        // Valid
+        let kfrag = KeyFrag::from_array(&vkfrag.to_array()).unwrap();
+        assert!(kfrag
+            .verify(&verifying_pk, Some(&delegating_pk), Some(&receiving_pk))
+            .is_ok());
+
+        let cfrag = CapsuleFrag::from_array(&vcfrag.to_array()).unwrap();
+        let vcfrag = cfrag
+            .verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk)
+            .unwrap();
+
+        assert_eq!(
+            capsule
+                .open_reencrypted(&receiving_sk, &delegating_pk, &[vcfrag.cfrag])
+                .unwrap()
+                .as_secret(),
+            capsule.open_original(&delegating_sk).as_secret()
+        );
+    }
+}
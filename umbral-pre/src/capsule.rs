@@ -10,6 +10,7 @@ use typenum::op;
 use crate::capsule_frag::CapsuleFrag;
 use crate::curve::{CurvePoint, CurveScalar};
 use crate::hashing_ds::{hash_capsule_points, hash_to_polynomial_arg, hash_to_shared_secret};
+use crate::key_frag::ReceiverTag;
 use crate::keys::{PublicKey, SecretKey};
 use crate::params::Parameters;
 use crate::secret_box::SecretBox;
@@ -21,6 +22,7 @@ use crate::traits::{
 
 /// Errors that can happen when opening a `Capsule` using reencrypted `CapsuleFrag` objects.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum OpenReencryptedError {
     /// An empty capsule fragment list is given.
     NoCapsuleFrags,
@@ -36,6 +38,9 @@ pub enum OpenReencryptedError {
     /// Can be caused by an incorrect (possibly modified) capsule
     /// or some of the capsule fragments.
     ValidationFailed,
+    /// Some of the capsule fragments were created from key fragments
+    /// generated for a different receiving key.
+    ReceiverMismatch,
 }
 
 impl fmt::Display for OpenReencryptedError {
@@ -47,11 +52,27 @@ impl fmt::Display for OpenReencryptedError {
             // Will be removed when #39 is fixed
             Self::ZeroHash => write!(f, "An internally hashed value is zero"),
             Self::ValidationFailed => write!(f, "Internal validation failed"),
+            Self::ReceiverMismatch => {
+                write!(f, "CapsuleFrags were not generated for this recipient")
+            }
         }
     }
 }
 
 /// Encapsulated symmetric key used to encrypt the plaintext.
+///
+/// Equality compares the normalized (affine) curve points,
+/// so it does not depend on how the capsule was obtained.
+/// Deserialization only accepts the canonical encoding
+/// (compressed points with coordinates reduced modulo the field order,
+/// and scalars reduced modulo the curve order),
+/// so equal capsules always have equal byte representations.
+///
+/// A capsule does not contain or commit to the public key it was created for:
+/// its points and scalar are derived from random values only,
+/// and the key only affects the encapsulated symmetric key.
+/// This is why the delegating public key has to be supplied separately
+/// when decrypting with capsule fragments.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Capsule {
     pub(crate) params: Parameters,
@@ -180,46 +201,44 @@ impl Capsule {
         SecretBox::new(shared_key.as_secret().to_array())
     }
 
-    #[allow(clippy::many_single_char_names)]
     pub(crate) fn open_reencrypted(
         &self,
         receiving_sk: &SecretKey,
         delegating_pk: &PublicKey,
         cfrags: &[CapsuleFrag],
     ) -> Result<SecretBox<KeySeed>, OpenReencryptedError> {
-        if cfrags.is_empty() {
-            return Err(OpenReencryptedError::NoCapsuleFrags);
-        }
-
-        let precursor = cfrags[0].precursor;
-
-        if !cfrags.iter().all(|cfrag| cfrag.precursor == precursor) {
-            return Err(OpenReencryptedError::MismatchedCapsuleFrags);
-        }
+        let precursor = common_precursor(cfrags)?;
 
         let pub_key = receiving_sk.public_key().to_point();
         let dh_point = &precursor * receiving_sk.to_secret_scalar().as_secret();
 
-        // Combination of CFrags via Shamir's Secret Sharing reconstruction
-        let mut lc = Vec::<CurveScalar>::with_capacity(cfrags.len());
-        for cfrag in cfrags {
-            let coeff = hash_to_polynomial_arg(&precursor, &pub_key, &dh_point, &cfrag.kfrag_id);
-            lc.push(coeff);
-        }
-
-        let mut e_prime = CurvePoint::identity();
-        let mut v_prime = CurvePoint::identity();
-        for (i, cfrag) in cfrags.iter().enumerate() {
-            // There is a minuscule probability that coefficients for two different frags are equal,
-            // in which case we'd rather fail gracefully.
-            let lambda_i =
-                lambda_coeff(&lc, i).ok_or(OpenReencryptedError::RepeatingCapsuleFrags)?;
-            e_prime = &e_prime + &(&cfrag.point_e1 * &lambda_i);
-            v_prime = &v_prime + &(&cfrag.point_v1 * &lambda_i);
-        }
+        let coefficients = combination_coefficients(&precursor, &pub_key, &dh_point, cfrags)?;
+        let (e_prime, v_prime) = combine_cfrags(cfrags, &coefficients);
+
+        self.open_combined(
+            delegating_pk,
+            &precursor,
+            &pub_key,
+            &dh_point,
+            &e_prime,
+            &v_prime,
+        )
+    }
 
+    /// Derives the symmetric key from the combined capsule fragments
+    /// (see [`combination_coefficients`] and [`combine_cfrags`]).
+    #[allow(clippy::many_single_char_names)]
+    pub(crate) fn open_combined(
+        &self,
+        delegating_pk: &PublicKey,
+        precursor: &CurvePoint,
+        pub_key: &CurvePoint,
+        dh_point: &CurvePoint,
+        e_prime: &CurvePoint,
+        v_prime: &CurvePoint,
+    ) -> Result<SecretBox<KeySeed>, OpenReencryptedError> {
         // Secret value 'd' allows to make Umbral non-interactive
-        let d = hash_to_shared_secret(&precursor, &pub_key, &dh_point);
+        let d = hash_to_shared_secret(precursor, pub_key, dh_point);
 
         let s = self.signature;
         let h = hash_capsule_points(&self.point_e, &self.point_v);
@@ -234,15 +253,81 @@ impl Capsule {
         // we'd rather fail gracefully than panic.
         let inv_d = inv_d_opt.ok_or(OpenReencryptedError::ZeroHash)?;
 
-        if &orig_pub_key * &(&s * &inv_d) != &(&e_prime * &h) + &v_prime {
+        if &orig_pub_key * &(&s * &inv_d) != &(e_prime * &h) + v_prime {
             return Err(OpenReencryptedError::ValidationFailed);
         }
 
-        let shared_key = SecretBox::new(&(&e_prime + &v_prime) * &d);
+        let shared_key = SecretBox::new(&(e_prime + v_prime) * &d);
         Ok(SecretBox::new(shared_key.as_secret().to_array()))
     }
 }
 
+/// Returns the precursor shared by all the given capsule fragments.
+pub(crate) fn common_precursor(cfrags: &[CapsuleFrag]) -> Result<CurvePoint, OpenReencryptedError> {
+    if cfrags.is_empty() {
+        return Err(OpenReencryptedError::NoCapsuleFrags);
+    }
+
+    let precursor = cfrags[0].precursor;
+
+    if !cfrags.iter().all(|cfrag| cfrag.precursor == precursor) {
+        return Err(OpenReencryptedError::MismatchedCapsuleFrags);
+    }
+
+    Ok(precursor)
+}
+
+/// Calculates the coefficients of the linear combination of capsule fragments
+/// (the Lagrange coefficients of Shamir's Secret Sharing reconstruction).
+/// Requires the receiver's secret key (through `dh_point`).
+pub(crate) fn combination_coefficients(
+    precursor: &CurvePoint,
+    pub_key: &CurvePoint,
+    dh_point: &CurvePoint,
+    cfrags: &[CapsuleFrag],
+) -> Result<Vec<CurveScalar>, OpenReencryptedError> {
+    // Catching the fragments made for a different receiver early
+    // (otherwise the validation of the result would fail without giving the reason).
+    let receiver_tag = ReceiverTag::new(precursor, pub_key, dh_point);
+    if cfrags
+        .iter()
+        .any(|cfrag| cfrag.receiver_tag != receiver_tag)
+    {
+        return Err(OpenReencryptedError::ReceiverMismatch);
+    }
+
+    let mut lc = Vec::<CurveScalar>::with_capacity(cfrags.len());
+    for cfrag in cfrags {
+        let coeff = hash_to_polynomial_arg(precursor, pub_key, dh_point, &cfrag.kfrag_id);
+        lc.push(coeff);
+    }
+
+    let mut coefficients = Vec::<CurveScalar>::with_capacity(cfrags.len());
+    for i in 0..cfrags.len() {
+        // There is a minuscule probability that coefficients for two different frags are equal,
+        // in which case we'd rather fail gracefully.
+        let lambda_i = lambda_coeff(&lc, i).ok_or(OpenReencryptedError::RepeatingCapsuleFrags)?;
+        coefficients.push(lambda_i);
+    }
+
+    Ok(coefficients)
+}
+
+/// Combines the capsule fragments using the coefficients from [`combination_coefficients`].
+/// Does not require any secret data.
+pub(crate) fn combine_cfrags(
+    cfrags: &[CapsuleFrag],
+    coefficients: &[CurveScalar],
+) -> (CurvePoint, CurvePoint) {
+    let mut e_prime = CurvePoint::identity();
+    let mut v_prime = CurvePoint::identity();
+    for (cfrag, lambda_i) in cfrags.iter().zip(coefficients.iter()) {
+        e_prime = &e_prime + &(&cfrag.point_e1 * lambda_i);
+        v_prime = &v_prime + &(&cfrag.point_v1 * lambda_i);
+    }
+    (e_prime, v_prime)
+}
+
 fn lambda_coeff(xs: &[CurveScalar], i: usize) -> Option<CurveScalar> {
     let mut res = CurveScalar::one();
     for j in 0..xs.len() {
@@ -263,6 +348,8 @@ mod tests {
     use rand_core::OsRng;
 
     use super::{Capsule, OpenReencryptedError};
+    use crate::bench::DummyRng;
+    use crate::curve::CurveScalar;
     use crate::serde::tests::{check_deserialization, check_serialization};
     use crate::serde::Representation;
     use crate::{
@@ -270,6 +357,12 @@ mod tests {
         SerializableToArray, Signer,
     };
 
+    const FIELD_MODULUS: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, 0xff, 0xff,
+        0xfc, 0x2f,
+    ];
+
     #[test]
     fn test_serialize() {
         let delegating_sk = SecretKey::random();
@@ -283,6 +376,37 @@ mod tests {
         assert_eq!(capsule, capsule_back);
     }
 
+    #[test]
+    fn test_equality() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+        let (capsule, _key_seed) = Capsule::from_public_key(&mut OsRng, &delegating_pk);
+
+        // Same points, but with a different internal (projective) representation.
+        let two = &CurveScalar::one() + &CurveScalar::one();
+        let two_inv = two.invert().unwrap();
+        let point_e = &(&capsule.point_e * &two) * &two_inv;
+        let point_v = &(&capsule.point_v * &two) * &two_inv;
+        let capsule2 = Capsule::new_verified(point_e, point_v, capsule.signature).unwrap();
+        assert_eq!(capsule, capsule2);
+        assert_eq!(capsule.to_array(), capsule2.to_array());
+
+        let capsule_arr = capsule.to_array();
+        assert_eq!(Capsule::from_array(&capsule_arr).unwrap(), capsule2);
+
+        // Non-canonical encodings are rejected on deserialization.
+
+        // A "hybrid" point tag instead of a compressed one
+        let mut capsule_arr_hybrid = capsule_arr;
+        capsule_arr_hybrid[0] += 4;
+        assert!(Capsule::from_array(&capsule_arr_hybrid).is_err());
+
+        // An x-coordinate equal to the field modulus (that is, not reduced)
+        let mut capsule_arr_unreduced = capsule_arr;
+        capsule_arr_unreduced[1..33].copy_from_slice(&FIELD_MODULUS);
+        assert!(Capsule::from_array(&capsule_arr_unreduced).is_err());
+    }
+
     #[test]
     fn test_open_reencrypted() {
         let delegating_sk = SecretKey::random();
@@ -300,7 +424,7 @@ mod tests {
 
         let vcfrags: Vec<_> = kfrags
             .iter()
-            .map(|kfrag| reencrypt(&capsule, &kfrag))
+            .map(|kfrag| reencrypt(&capsule, kfrag))
             .collect();
 
         let cfrags: Vec<_> = vcfrags.iter().cloned().map(|vcfrag| vcfrag.cfrag).collect();
@@ -313,7 +437,7 @@ mod tests {
         // Empty cfrag vector
         let result = capsule.open_reencrypted(&receiving_sk, &delegating_pk, &[]);
         assert_eq!(
-            result.map(|x| x.as_secret().clone()),
+            result.map(|x| *x.as_secret()),
             Err(OpenReencryptedError::NoCapsuleFrags)
         );
 
@@ -322,7 +446,7 @@ mod tests {
 
         let vcfrags2: Vec<_> = kfrags2
             .iter()
-            .map(|kfrag| reencrypt(&capsule, &kfrag))
+            .map(|kfrag| reencrypt(&capsule, kfrag))
             .collect();
 
         let mismatched_cfrags: Vec<_> = vcfrags[0..1]
@@ -334,7 +458,7 @@ mod tests {
 
         let result = capsule.open_reencrypted(&receiving_sk, &delegating_pk, &mismatched_cfrags);
         assert_eq!(
-            result.map(|x| x.as_secret().clone()),
+            result.map(|x| *x.as_secret()),
             Err(OpenReencryptedError::MismatchedCapsuleFrags)
         );
 
@@ -342,11 +466,27 @@ mod tests {
         let (capsule2, _key_seed) = Capsule::from_public_key(&mut OsRng, &delegating_pk);
         let result = capsule2.open_reencrypted(&receiving_sk, &delegating_pk, &cfrags);
         assert_eq!(
-            result.map(|x| x.as_secret().clone()),
+            result.map(|x| *x.as_secret()),
             Err(OpenReencryptedError::ValidationFailed)
         );
     }
 
+    #[test]
+    fn test_independent_of_public_key() {
+        // The capsule is the same for any delegating key given the same randomness,
+        // so the delegating key cannot be recovered from it.
+        let delegating_pk1 = SecretKey::random().public_key();
+        let delegating_pk2 = SecretKey::random().public_key();
+
+        let mut rng1 = DummyRng::new(b"seed", b"capsule");
+        let mut rng2 = DummyRng::new(b"seed", b"capsule");
+        let (capsule1, key_seed1) = Capsule::from_public_key(&mut rng1, &delegating_pk1);
+        let (capsule2, key_seed2) = Capsule::from_public_key(&mut rng2, &delegating_pk2);
+
+        assert_eq!(capsule1, capsule2);
+        assert_ne!(key_seed1.as_secret(), key_seed2.as_secret());
+    }
+
     #[test]
     fn test_serde_serialization() {
         let delegating_sk = SecretKey::random();
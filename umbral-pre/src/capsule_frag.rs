@@ -1,20 +1,25 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::fmt;
 
 use generic_array::sequence::Concat;
 use generic_array::GenericArray;
 use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use typenum::op;
+use typenum::{op, Unsigned};
 
 use crate::capsule::Capsule;
 use crate::curve::{CurvePoint, CurveScalar};
-use crate::hashing_ds::{hash_to_cfrag_verification, kfrag_signature_message};
-use crate::key_frag::{KeyFrag, KeyFragID};
-use crate::keys::{PublicKey, Signature};
+use crate::hashing_ds::{
+    hash_to_cfrag_verification, kfrag_signature_message_keys, kfrag_signature_message_with_keys,
+};
+use crate::key_frag::{KeyFrag, KeyFragCommitment, KeyFragID, ReceiverTag, ReceiverTagSize};
+use crate::keys::{PublicKey, SecretKey, Signature};
+use crate::metrics::{record, record_outcome, Operation};
 use crate::serde::{serde_deserialize, serde_serialize, Representation};
 use crate::traits::{
     fmt_public, ConstructionError, DeserializableFromArray, DeserializationError, HasTypeName,
-    RepresentableAsArray, SerializableToArray,
+    RepresentableAsArray, SerializableToArray, SizeMismatchError,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -121,11 +126,17 @@ pub struct CapsuleFrag {
     pub(crate) point_v1: CurvePoint,
     pub(crate) kfrag_id: KeyFragID,
     pub(crate) precursor: CurvePoint,
+    pub(crate) receiver_tag: ReceiverTag,
     pub(crate) proof: CapsuleFragProof,
 }
 
 impl RepresentableAsArray for CapsuleFrag {
-    type Size = op!(PointSize + PointSize + ScalarSize + PointSize + CapsuleFragProofSize);
+    type Size = op!(PointSize
+        + PointSize
+        + ScalarSize
+        + PointSize
+        + ReceiverTagSize
+        + CapsuleFragProofSize);
 }
 
 impl SerializableToArray for CapsuleFrag {
@@ -135,6 +146,7 @@ impl SerializableToArray for CapsuleFrag {
             .concat(self.point_v1.to_array())
             .concat(self.kfrag_id.to_array())
             .concat(self.precursor.to_array())
+            .concat(self.receiver_tag.to_array())
             .concat(self.proof.to_array())
     }
 }
@@ -145,12 +157,14 @@ impl DeserializableFromArray for CapsuleFrag {
         let (point_v1, rest) = CurvePoint::take(rest)?;
         let (kfrag_id, rest) = KeyFragID::take(rest)?;
         let (precursor, rest) = CurvePoint::take(rest)?;
+        let (receiver_tag, rest) = ReceiverTag::take(rest)?;
         let proof = CapsuleFragProof::take_last(rest)?;
         Ok(Self {
             point_e1,
             point_v1,
             kfrag_id,
             precursor,
+            receiver_tag,
             proof,
         })
     }
@@ -220,13 +234,16 @@ impl CapsuleFrag {
             point_v1: v1,
             kfrag_id: kfrag.id,
             precursor: kfrag.precursor,
+            receiver_tag: kfrag.receiver_tag,
             proof,
         }
     }
 
     /// Verifies the integrity of the capsule fragment, given the original capsule,
     /// the encrypting party's key, the decrypting party's key, and the signing key.
-    #[allow(clippy::many_single_char_names)]
+    ///
+    /// If multiple capsule fragments need to be verified against the same keys,
+    /// consider using [`CapsuleFragVerifier`].
     pub fn verify(
         &self,
         capsule: &Capsule,
@@ -234,61 +251,187 @@ impl CapsuleFrag {
         delegating_pk: &PublicKey,
         receiving_pk: &PublicKey,
     ) -> Result<VerifiedCapsuleFrag, CapsuleFragVerificationError> {
-        let params = capsule.params;
-
-        // Here are the formulaic constituents shared with
-        // `CapsuleFragProof::from_kfrag_and_cfrag`.
-
-        let e = capsule.point_e;
-        let v = capsule.point_v;
+        CapsuleFragVerifier::new(capsule, verifying_pk, delegating_pk, receiving_pk).verify(self)
+    }
 
-        let e1 = self.point_e1;
-        let v1 = self.point_v1;
+    /// Verifies that the capsule fragment is a correct reencryption of the given capsule
+    /// with the key fragment having the given commitment
+    /// (see [`KeyFrag::commitment`](`crate::KeyFrag::commitment`)).
+    ///
+    /// Unlike [`verify`](`Self::verify`), does not check the signature of the delegating party,
+    /// so the commitment must come from the delegating party itself,
+    /// or through a channel the delegating party authenticates.
+    /// A commitment supplied by the proxy proves nothing, since the proxy can commit
+    /// to a key fragment of its own.
+    pub fn verify_against_commitment(
+        &self,
+        capsule: &Capsule,
+        commitment: &KeyFragCommitment,
+    ) -> bool {
+        let verified =
+            self.proof.kfrag_commitment == commitment.0 && verify_reencryption_proof(capsule, self);
+        record_outcome(Operation::Verify, verified);
+        verified
+    }
 
-        let u = params.u;
-        let u1 = self.proof.kfrag_commitment;
+    /// Checks only the signature of the delegating party carried by the capsule fragment
+    /// (the one made over the key fragment it was produced from),
+    /// without checking the proof of correct reencryption.
+    ///
+    /// This is a cheap pre-filter for relays, allowing them to drop forged fragments early;
+    /// it is not a replacement for [`verify`](`Self::verify`).
+    /// A fragment passing this check may still be an incorrect reencryption,
+    /// or a reencryption of a different capsule.
+    ///
+    /// The fragments are not signed by the nodes producing them,
+    /// and the signature of the delegating party always covers
+    /// the delegating and the receiving keys, so both are required.
+    pub fn verify_signature_only(
+        &self,
+        verifying_pk: &PublicKey,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+    ) -> bool {
+        let verified = verify_kfrag_signature(
+            self,
+            verifying_pk,
+            &kfrag_signature_message_keys(Some(delegating_pk), Some(receiving_pk)),
+        );
+        record_outcome(Operation::Verify, verified);
+        verified
+    }
 
-        let e2 = self.proof.point_e2;
-        let v2 = self.proof.point_v2;
-        let u2 = self.proof.kfrag_pok;
+    /// Returns `true` if the capsule fragment was created from a key fragment
+    /// generated for the public key of `receiving_sk`.
+    ///
+    /// The data this check is based on is covered by the signature of the delegating party,
+    /// so the result can only be trusted for a verified fragment
+    /// (see [`VerifiedCapsuleFrag::is_for_receiver`]).
+    pub fn is_for_receiver(&self, receiving_sk: &SecretKey) -> bool {
+        self.receiver_tag == ReceiverTag::for_receiver(&self.precursor, receiving_sk)
+    }
+}
 
-        let h = hash_to_cfrag_verification(&[e, e1, e2, v, v1, v2, u, u1, u2]);
+/// A verification context for multiple [`CapsuleFrag`] objects
+/// originating from the same capsule and the same delegation.
+///
+/// Prepares the data that only depends on the capsule and the keys once,
+/// so that the cost of verifying each capsule fragment is reduced.
+#[derive(Clone, Debug)]
+pub struct CapsuleFragVerifier {
+    capsule: Capsule,
+    verifying_pk: PublicKey,
+    keys_message: Box<[u8]>,
+}
 
-        ///////
+impl CapsuleFragVerifier {
+    /// Creates a verification context given the original capsule,
+    /// the encrypting party's key, the decrypting party's key, and the signing key.
+    pub fn new(
+        capsule: &Capsule,
+        verifying_pk: &PublicKey,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+    ) -> Self {
+        Self {
+            capsule: *capsule,
+            verifying_pk: *verifying_pk,
+            keys_message: kfrag_signature_message_keys(Some(delegating_pk), Some(receiving_pk)),
+        }
+    }
 
-        let precursor = self.precursor;
-        let kfrag_id = self.kfrag_id;
+    /// Verifies the integrity of the capsule fragment.
+    /// Equivalent to [`CapsuleFrag::verify`] called with the keys this context was created with.
+    pub fn verify(
+        &self,
+        cfrag: &CapsuleFrag,
+    ) -> Result<VerifiedCapsuleFrag, CapsuleFragVerificationError> {
+        record(Operation::Verify, self.verify_impl(cfrag))
+    }
 
-        if !self.proof.kfrag_signature.verify(
-            verifying_pk,
-            kfrag_signature_message(
-                &kfrag_id,
-                &u1,
-                &precursor,
-                Some(delegating_pk),
-                Some(receiving_pk),
-            )
-            .as_ref(),
-        ) {
+    pub(crate) fn verify_impl(
+        &self,
+        cfrag: &CapsuleFrag,
+    ) -> Result<VerifiedCapsuleFrag, CapsuleFragVerificationError> {
+        if !verify_kfrag_signature(cfrag, &self.verifying_pk, &self.keys_message) {
             return Err(CapsuleFragVerificationError::IncorrectKeyFragSignature);
         }
 
-        // TODO (#46): if one or more of the values here are incorrect,
-        // we'll get the wrong `h` (since they're all hashed into it),
-        // so perhaps it's enough to check only one of these equations.
-        let z = self.proof.signature;
-        let correct_reencryption_of_e = &e * &z == &e2 + &(&e1 * &h);
-        let correct_reencryption_of_v = &v * &z == &v2 + &(&v1 * &h);
-        let correct_rk_commitment = &u * &z == &u2 + &(&u1 * &h);
-
-        if !(correct_reencryption_of_e & correct_reencryption_of_v & correct_rk_commitment) {
+        if !verify_reencryption_proof(&self.capsule, cfrag) {
             return Err(CapsuleFragVerificationError::IncorrectReencryption);
         }
 
         Ok(VerifiedCapsuleFrag {
-            cfrag: self.clone(),
+            cfrag: cfrag.clone(),
         })
     }
+
+    /// Lazily verifies a stream of capsule fragments,
+    /// yielding the verification result for each of them.
+    ///
+    /// Chain with `.filter_map(Result::ok)` to skip the invalid fragments,
+    /// or collect into a `Result` to stop at the first invalid one.
+    pub fn verify_iter<'a>(
+        &'a self,
+        cfrags: impl IntoIterator<Item = CapsuleFrag> + 'a,
+    ) -> impl Iterator<Item = Result<VerifiedCapsuleFrag, CapsuleFragVerificationError>> + 'a {
+        cfrags.into_iter().map(move |cfrag| self.verify(&cfrag))
+    }
+}
+
+/// Verifies the signature of the delegating party over the key fragment
+/// the capsule fragment was created from.
+fn verify_kfrag_signature(
+    cfrag: &CapsuleFrag,
+    verifying_pk: &PublicKey,
+    keys_message: &[u8],
+) -> bool {
+    cfrag.proof.kfrag_signature.verify(
+        verifying_pk,
+        kfrag_signature_message_with_keys(
+            &cfrag.kfrag_id,
+            &cfrag.proof.kfrag_commitment,
+            &cfrag.precursor,
+            &cfrag.receiver_tag,
+            keys_message,
+        )
+        .as_ref(),
+    )
+}
+
+/// Verifies the proof of correct reencryption of the capsule
+/// with the key fragment committed to in the proof.
+#[allow(clippy::many_single_char_names)]
+fn verify_reencryption_proof(capsule: &Capsule, cfrag: &CapsuleFrag) -> bool {
+    let params = capsule.params;
+
+    // Here are the formulaic constituents shared with
+    // `CapsuleFragProof::from_kfrag_and_cfrag`.
+
+    let e = capsule.point_e;
+    let v = capsule.point_v;
+
+    let e1 = cfrag.point_e1;
+    let v1 = cfrag.point_v1;
+
+    let u = params.u;
+    let u1 = cfrag.proof.kfrag_commitment;
+
+    let e2 = cfrag.proof.point_e2;
+    let v2 = cfrag.proof.point_v2;
+    let u2 = cfrag.proof.kfrag_pok;
+
+    let h = hash_to_cfrag_verification(&[e, e1, e2, v, v1, v2, u, u1, u2]);
+
+    // TODO (#46): if one or more of the values here are incorrect,
+    // we'll get the wrong `h` (since they're all hashed into it),
+    // so perhaps it's enough to check only one of these equations.
+    let z = cfrag.proof.signature;
+    let correct_reencryption_of_e = &e * &z == &e2 + &(&e1 * &h);
+    let correct_reencryption_of_v = &v * &z == &v2 + &(&v1 * &h);
+    let correct_rk_commitment = &u * &z == &u2 + &(&u1 * &h);
+
+    correct_reencryption_of_e & correct_reencryption_of_v & correct_rk_commitment
 }
 
 /// Verified capsule fragment, good for dencryption.
@@ -340,6 +483,181 @@ impl VerifiedCapsuleFrag {
     pub fn from_verified_bytes(data: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
         CapsuleFrag::from_bytes(data).map(|cfrag| Self { cfrag })
     }
+
+    /// Returns `true` if the capsule fragment was created from a key fragment
+    /// generated for the public key of `receiving_sk`.
+    ///
+    /// Can be used to pick the right key before decryption;
+    /// [`decrypt_reencrypted`](`crate::decrypt_reencrypted`) performs the same check
+    /// and returns [`OpenReencryptedError::ReceiverMismatch`](`crate::OpenReencryptedError::ReceiverMismatch`)
+    /// if it fails.
+    pub fn is_for_receiver(&self, receiving_sk: &SecretKey) -> bool {
+        self.cfrag.is_for_receiver(receiving_sk)
+    }
+
+    /// Clears the verification status from the capsule frag.
+    /// Useful for the cases where it needs to be put in the protocol structure
+    /// containing [`CapsuleFrag`] types (since those are the ones
+    /// that can be serialized/deserialized freely).
+    pub fn unverify(self) -> CapsuleFrag {
+        self.cfrag
+    }
+}
+
+// Increased on any change of the compact format.
+const COMPACT_FORMAT_VERSION: u8 = 1;
+
+// The components shared by all the capsule fragments of the same delegation:
+// the precursor and the receiver tag.
+type CompactHeaderSize = op!(PointSize + ReceiverTagSize);
+// The rest of the capsule fragment.
+type CompactEntrySize = op!(PointSize + PointSize + ScalarSize + CapsuleFragProofSize);
+
+/// Possible errors that can be returned by [`serialize_cfrags_compact`].
+#[derive(Debug, PartialEq)]
+pub enum CompactCapsuleFragsError {
+    /// An empty capsule fragment list is given.
+    NoCapsuleFrags,
+    /// Capsule fragments originate from different delegations.
+    MismatchedDelegations,
+}
+
+impl fmt::Display for CompactCapsuleFragsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoCapsuleFrags => write!(f, "Empty CapsuleFrag sequence"),
+            Self::MismatchedDelegations => {
+                write!(f, "CapsuleFrags originate from different delegations")
+            }
+        }
+    }
+}
+
+/// Serializes capsule fragments of the same delegation into a single bytestring.
+///
+/// The precursor and the receiver tag, which are the same
+/// for all the fragments of a delegation, are only stored once,
+/// so the result for `n` fragments is `65 * (n - 1) - 1` bytes shorter
+/// than the concatenation of the fragments serialized separately
+/// (the extra byte being the format version tag).
+/// The rest of the fragment data is unique to each fragment and is stored as is.
+///
+/// Note that the deserialized fragments have to be verified again before use.
+pub fn serialize_cfrags_compact(
+    cfrags: &[CapsuleFrag],
+) -> Result<Box<[u8]>, CompactCapsuleFragsError> {
+    let first = cfrags
+        .first()
+        .ok_or(CompactCapsuleFragsError::NoCapsuleFrags)?;
+    if cfrags
+        .iter()
+        .any(|cfrag| cfrag.precursor != first.precursor || cfrag.receiver_tag != first.receiver_tag)
+    {
+        return Err(CompactCapsuleFragsError::MismatchedDelegations);
+    }
+
+    let mut result = Vec::<u8>::with_capacity(
+        1 + CompactHeaderSize::to_usize() + CompactEntrySize::to_usize() * cfrags.len(),
+    );
+    result.push(COMPACT_FORMAT_VERSION);
+    result.extend_from_slice(&first.precursor.to_array());
+    result.extend_from_slice(&first.receiver_tag.to_array());
+    for cfrag in cfrags {
+        let entry: GenericArray<u8, CompactEntrySize> = cfrag
+            .point_e1
+            .to_array()
+            .concat(cfrag.point_v1.to_array())
+            .concat(cfrag.kfrag_id.to_array())
+            .concat(cfrag.proof.to_array());
+        result.extend_from_slice(&entry);
+    }
+
+    Ok(result.into_boxed_slice())
+}
+
+/// Restores the capsule fragments serialized by [`serialize_cfrags_compact`].
+///
+/// Returns [`DeserializationError::UnsupportedVersion`] if the data was created
+/// by a version of the library using a different compact format.
+pub fn deserialize_cfrags_compact(
+    data: impl AsRef<[u8]>,
+) -> Result<Box<[CapsuleFrag]>, DeserializationError> {
+    let (version, data) = data
+        .as_ref()
+        .split_first()
+        .ok_or_else(|| DeserializationError::SizeMismatch(SizeMismatchError::new(0, 1)))?;
+    if *version != COMPACT_FORMAT_VERSION {
+        return Err(DeserializationError::UnsupportedVersion(*version));
+    }
+
+    let header_size = CompactHeaderSize::to_usize();
+    let entry_size = CompactEntrySize::to_usize();
+
+    if data.len() < header_size + entry_size || (data.len() - header_size) % entry_size != 0 {
+        let entries = core::cmp::max((data.len().saturating_sub(header_size)) / entry_size, 1);
+        // Including the version tag in the sizes.
+        return Err(DeserializationError::SizeMismatch(SizeMismatchError::new(
+            1 + data.len(),
+            1 + header_size + entry_size * entries,
+        )));
+    }
+
+    let (header, entries) = data.split_at(header_size);
+    let header = GenericArray::<u8, CompactHeaderSize>::from_slice(header);
+    let (precursor, rest) =
+        CurvePoint::take(*header).map_err(DeserializationError::ConstructionFailure)?;
+    let receiver_tag =
+        ReceiverTag::take_last(rest).map_err(DeserializationError::ConstructionFailure)?;
+
+    entries
+        .chunks(entry_size)
+        .map(|entry| {
+            let entry = GenericArray::<u8, CompactEntrySize>::from_slice(entry);
+            let (point_e1, rest) = CurvePoint::take(*entry)?;
+            let (point_v1, rest) = CurvePoint::take(rest)?;
+            let (kfrag_id, rest) = KeyFragID::take(rest)?;
+            let proof = CapsuleFragProof::take_last(rest)?;
+            Ok(CapsuleFrag {
+                point_e1,
+                point_v1,
+                kfrag_id,
+                precursor,
+                receiver_tag,
+                proof,
+            })
+        })
+        .collect::<Result<Vec<_>, ConstructionError>>()
+        .map(|cfrags| cfrags.into_boxed_slice())
+        .map_err(DeserializationError::ConstructionFailure)
+}
+
+/// Splits a concatenation of serialized capsule fragments
+/// (e.g. a batched response of a node) into separate fragments.
+///
+/// Capsule fragments have a fixed serialized size
+/// ([`CapsuleFrag::serialized_size`](`crate::RepresentableAsArray::serialized_size`)),
+/// so no framing is needed. An empty bytestring results in an empty list.
+/// If the size of the data is not a multiple of the fragment size,
+/// [`DeserializationError::SizeMismatch`] is returned,
+/// with the expected size being that of the data with the last fragment complete.
+///
+/// Note that the fragments have to be verified before use.
+pub fn parse_cfrags(data: impl AsRef<[u8]>) -> Result<Box<[CapsuleFrag]>, DeserializationError> {
+    let data = data.as_ref();
+    let cfrag_size = CapsuleFrag::serialized_size();
+
+    let remainder = data.len() % cfrag_size;
+    if remainder != 0 {
+        return Err(DeserializationError::SizeMismatch(SizeMismatchError::new(
+            data.len(),
+            data.len() - remainder + cfrag_size,
+        )));
+    }
+
+    data.chunks(cfrag_size)
+        .map(CapsuleFrag::from_bytes)
+        .collect::<Result<Vec<_>, _>>()
+        .map(|cfrags| cfrags.into_boxed_slice())
 }
 
 #[cfg(test)]
@@ -348,12 +666,17 @@ mod tests {
     use alloc::boxed::Box;
     use alloc::vec::Vec;
 
-    use super::{CapsuleFrag, VerifiedCapsuleFrag};
+    use super::{
+        deserialize_cfrags_compact, parse_cfrags, serialize_cfrags_compact, CapsuleFrag,
+        CapsuleFragVerificationError, CapsuleFragVerifier, CompactCapsuleFragsError,
+        VerifiedCapsuleFrag,
+    };
     use crate::serde::tests::{check_deserialization, check_serialization};
     use crate::serde::Representation;
     use crate::{
-        encrypt, generate_kfrags, reencrypt, Capsule, DeserializableFromArray, PublicKey,
-        SecretKey, SerializableToArray, Signer,
+        encrypt, generate_kfrags, reencrypt, Capsule, DeserializableFromArray,
+        DeserializationError, KeyFragCommitment, PublicKey, RepresentableAsArray, SecretKey,
+        SerializableToArray, Signer, SizeMismatchError,
     };
 
     fn prepare_cfrags() -> (
@@ -380,7 +703,7 @@ mod tests {
 
         let verified_cfrags: Vec<_> = kfrags
             .iter()
-            .map(|kfrag| reencrypt(&capsule, &kfrag))
+            .map(|kfrag| reencrypt(&capsule, kfrag))
             .collect();
 
         (
@@ -397,7 +720,7 @@ mod tests {
         let (delegating_pk, receiving_pk, verifying_pk, capsule, verified_cfrags) =
             prepare_cfrags();
 
-        for verified_cfrag in verified_cfrags.iter().cloned() {
+        for verified_cfrag in verified_cfrags.iter() {
             let cfrag_array = verified_cfrag.to_array();
             let cfrag_back = CapsuleFrag::from_array(&cfrag_array).unwrap();
 
@@ -407,8 +730,237 @@ mod tests {
                 .verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk)
                 .unwrap();
 
-            assert_eq!(verified_cfrag_back, verified_cfrag);
+            assert_eq!(&verified_cfrag_back, verified_cfrag);
+        }
+    }
+
+    #[test]
+    fn test_verifier() {
+        let (delegating_pk, receiving_pk, verifying_pk, capsule, verified_cfrags) =
+            prepare_cfrags();
+
+        let verifier =
+            CapsuleFragVerifier::new(&capsule, &verifying_pk, &delegating_pk, &receiving_pk);
+
+        for verified_cfrag in verified_cfrags.iter() {
+            let cfrag = CapsuleFrag::from_array(&verified_cfrag.to_array()).unwrap();
+            let verified_cfrag_back = verifier.verify(&cfrag).unwrap();
+            assert_eq!(&verified_cfrag_back, verified_cfrag);
+        }
+
+        // A stream with a mix of valid and invalid cfrags
+        let (_, _, _, _, other_cfrags) = prepare_cfrags();
+        let stream = [
+            verified_cfrags[0].clone().unverify(),
+            other_cfrags[0].clone().unverify(),
+            verified_cfrags[1].clone().unverify(),
+        ]
+        .to_vec();
+        let results: Vec<_> = verifier.verify_iter(stream.clone()).collect();
+        assert_eq!(results[0].as_ref(), Ok(&verified_cfrags[0]));
+        assert_eq!(
+            results[1],
+            Err(CapsuleFragVerificationError::IncorrectKeyFragSignature)
+        );
+        assert_eq!(results[2].as_ref(), Ok(&verified_cfrags[1]));
+
+        let valid: Vec<_> = verifier
+            .verify_iter(stream.clone())
+            .filter_map(Result::ok)
+            .collect();
+        assert_eq!(valid, verified_cfrags[0..2]);
+
+        let all_valid: Result<Vec<_>, _> = verifier.verify_iter(stream).collect();
+        assert!(all_valid.is_err());
+
+        // A context created with wrong keys rejects the same cfrags
+        let wrong_pk = SecretKey::random().public_key();
+        let verifier = CapsuleFragVerifier::new(&capsule, &verifying_pk, &wrong_pk, &receiving_pk);
+        let cfrag = CapsuleFrag::from_array(&verified_cfrags[0].to_array()).unwrap();
+        assert_eq!(
+            verifier.verify(&cfrag),
+            Err(CapsuleFragVerificationError::IncorrectKeyFragSignature)
+        );
+    }
+
+    #[test]
+    fn test_receiver_tag() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+        let signer = Signer::new(&SecretKey::random());
+        let verifying_pk = signer.verifying_key();
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let (capsule, _ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let verified_cfrag = reencrypt(&capsule, &kfrags[0]);
+
+        assert!(verified_cfrag.is_for_receiver(&receiving_sk));
+        assert!(!verified_cfrag.is_for_receiver(&SecretKey::random()));
+
+        // The tag is covered by the signature
+        let mut cfrag_bytes = verified_cfrag.to_array();
+        // The tag follows `point_e1`, `point_v1`, `kfrag_id` and `precursor`
+        cfrag_bytes[33 + 33 + 32 + 33] ^= 1;
+        let cfrag = CapsuleFrag::from_array(&cfrag_bytes).unwrap();
+        assert!(!cfrag.is_for_receiver(&receiving_sk));
+        assert_eq!(
+            cfrag.verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk),
+            Err(CapsuleFragVerificationError::IncorrectKeyFragSignature)
+        );
+    }
+
+    #[test]
+    fn test_verify_signature_only() {
+        let (delegating_pk, receiving_pk, verifying_pk, capsule, verified_cfrags) =
+            prepare_cfrags();
+        let other_pk = SecretKey::random().public_key();
+
+        let cfrag = CapsuleFrag::from_array(&verified_cfrags[0].to_array()).unwrap();
+
+        assert!(cfrag.verify_signature_only(&verifying_pk, &delegating_pk, &receiving_pk));
+        assert!(!cfrag.verify_signature_only(&other_pk, &delegating_pk, &receiving_pk));
+        assert!(!cfrag.verify_signature_only(&verifying_pk, &other_pk, &receiving_pk));
+        assert!(!cfrag.verify_signature_only(&verifying_pk, &delegating_pk, &other_pk));
+
+        // The reencryption itself is not checked
+        let (other_capsule, _ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+        assert!(cfrag
+            .verify(&other_capsule, &verifying_pk, &delegating_pk, &receiving_pk)
+            .is_err());
+        assert!(cfrag.verify_signature_only(&verifying_pk, &delegating_pk, &receiving_pk));
+        assert!(cfrag
+            .verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_verify_against_commitment() {
+        let delegating_sk = SecretKey::random();
+        let receiving_pk = SecretKey::random().public_key();
+        let signer = Signer::new(&SecretKey::random());
+
+        let (capsule, _ciphertext) =
+            encrypt(&delegating_sk.public_key(), b"peace at dawn").unwrap();
+        let (other_capsule, _ciphertext) =
+            encrypt(&delegating_sk.public_key(), b"peace at dawn").unwrap();
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+
+        // Published by the proxies
+        let commitment0 = kfrags[0].commitment();
+        let commitment1 = kfrags[1].commitment();
+        let commitment0 = KeyFragCommitment::from_array(&commitment0.to_array()).unwrap();
+
+        let cfrag = CapsuleFrag::from_array(&reencrypt(&capsule, &kfrags[0]).to_array()).unwrap();
+
+        assert!(cfrag.verify_against_commitment(&capsule, &commitment0));
+
+        // Commitment to a different kfrag
+        assert!(!cfrag.verify_against_commitment(&capsule, &commitment1));
+
+        // A different capsule
+        assert!(!cfrag.verify_against_commitment(&other_capsule, &commitment0));
+
+        check_serialization(&commitment0, Representation::Base64);
+        check_deserialization(&commitment0);
+    }
+
+    #[test]
+    fn test_compact_serialization() {
+        let (delegating_pk, receiving_pk, verifying_pk, capsule, verified_cfrags) =
+            prepare_cfrags();
+        let cfrags: Vec<_> = verified_cfrags
+            .iter()
+            .cloned()
+            .map(|vcfrag| vcfrag.unverify())
+            .collect();
+
+        let compact = serialize_cfrags_compact(&cfrags).unwrap();
+        let naive_size = cfrags.len() * CapsuleFrag::serialized_size();
+        assert_eq!(compact.len(), naive_size - 65 * (cfrags.len() - 1) + 1);
+
+        let cfrags_back = deserialize_cfrags_compact(&compact).unwrap();
+        assert_eq!(cfrags_back.as_ref(), cfrags.as_slice());
+        for cfrag in cfrags_back.iter() {
+            assert!(cfrag
+                .verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk)
+                .is_ok());
+        }
+
+        // Truncated data
+        assert!(deserialize_cfrags_compact(&compact[..compact.len() - 1]).is_err());
+        assert!(deserialize_cfrags_compact(&compact[..66]).is_err());
+        assert!(deserialize_cfrags_compact(b"").is_err());
+
+        // A bumped format version
+        let mut bumped = compact.to_vec();
+        bumped[0] += 1;
+        assert_eq!(
+            deserialize_cfrags_compact(&bumped),
+            Err(DeserializationError::UnsupportedVersion(bumped[0]))
+        );
+
+        assert_eq!(
+            serialize_cfrags_compact(&[]),
+            Err(CompactCapsuleFragsError::NoCapsuleFrags)
+        );
+
+        // Fragments of a different delegation cannot be serialized together
+        let (_, _, _, _, other_cfrags) = prepare_cfrags();
+        let mixed = [cfrags[0].clone(), other_cfrags[0].clone().unverify()];
+        assert_eq!(
+            serialize_cfrags_compact(&mixed),
+            Err(CompactCapsuleFragsError::MismatchedDelegations)
+        );
+    }
+
+    #[test]
+    fn test_parse_cfrags() {
+        let (_, _, _, _, verified_cfrags) = prepare_cfrags();
+        let cfrags: Vec<_> = verified_cfrags
+            .iter()
+            .cloned()
+            .map(|vcfrag| vcfrag.unverify())
+            .collect();
+
+        let blob: Vec<u8> = cfrags
+            .iter()
+            .flat_map(|cfrag| cfrag.to_array().to_vec())
+            .collect();
+        let cfrags_back = parse_cfrags(&blob).unwrap();
+        assert_eq!(cfrags_back.as_ref(), cfrags.as_slice());
+
+        assert!(parse_cfrags(b"").unwrap().is_empty());
+
+        // A truncated tail
+        let cfrag_size = CapsuleFrag::serialized_size();
+        assert_eq!(
+            parse_cfrags(&blob[..blob.len() - 1]),
+            Err(DeserializationError::SizeMismatch(SizeMismatchError::new(
+                blob.len() - 1,
+                blob.len()
+            )))
+        );
+        assert_eq!(
+            parse_cfrags(&blob[..cfrag_size + 1]),
+            Err(DeserializationError::SizeMismatch(SizeMismatchError::new(
+                cfrag_size + 1,
+                cfrag_size * 2
+            )))
+        );
+
+        // A corrupted fragment in the middle
+        let mut corrupted = blob.clone();
+        for byte in corrupted[cfrag_size..cfrag_size * 2].iter_mut() {
+            *byte = 0xff;
         }
+        assert!(matches!(
+            parse_cfrags(&corrupted),
+            Err(DeserializationError::ConstructionFailure(_))
+        ));
     }
 
     #[test]
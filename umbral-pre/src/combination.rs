@@ -0,0 +1,390 @@
+//! Splitting the combination of capsule fragments between the receiver and a helper.
+//!
+//! The most expensive part of [`decrypt_reencrypted`](`crate::decrypt_reencrypted`)
+//! is combining the capsule fragments, which takes two scalar multiplications per fragment.
+//! The coefficients of the combination depend on the receiver's secret key,
+//! but the combination itself does not, so it can be offloaded to an untrusted helper:
+//!
+//! 1. the receiver calls [`combination_coefficients`] (one scalar multiplication);
+//! 2. the helper calls [`combine_cfrags`] with the capsule fragments and the coefficients;
+//! 3. the receiver calls [`decrypt_combined`](`crate::decrypt_combined`)
+//!    with the result and its own copy of the fragments (three scalar multiplications).
+//!
+//! What the helper learns: the capsule fragments (which are not secret,
+//! since they are seen by the proxies anyway), the coefficients and the combined fragments.
+//! Neither of those allows the helper to derive the symmetric key,
+//! since that requires a value only the receiver can calculate (a Diffie-Hellman point
+//! of the receiver's secret key and the fragments' precursor).
+//! The helper does learn which fragments were used for the decryption.
+//!
+//! A malicious helper cannot make the receiver accept an incorrect plaintext:
+//! a modified combination will make [`decrypt_combined`](`crate::decrypt_combined`) fail,
+//! either on the internal validation of the capsule, or on the authentication of the ciphertext.
+//! This relies on the precursor being taken from the receiver's verified fragments
+//! (which is why they are passed to [`decrypt_combined`](`crate::decrypt_combined`)):
+//! a helper choosing the precursor itself could produce a combination passing the validation.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use generic_array::sequence::Concat;
+use generic_array::GenericArray;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use typenum::op;
+
+use crate::capsule::{self, OpenReencryptedError};
+use crate::capsule_frag::VerifiedCapsuleFrag;
+use crate::curve::{CurvePoint, CurveScalar};
+use crate::keys::SecretKey;
+use crate::serde::{serde_deserialize, serde_serialize, Representation};
+use crate::traits::{
+    fmt_public, ConstructionError, DeserializableFromArray, HasTypeName, RepresentableAsArray,
+    SerializableToArray,
+};
+
+/// A coefficient of the combination of a single capsule fragment,
+/// produced by [`combination_coefficients`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CombinationCoefficient(CurveScalar);
+
+impl RepresentableAsArray for CombinationCoefficient {
+    type Size = <CurveScalar as RepresentableAsArray>::Size;
+}
+
+impl SerializableToArray for CombinationCoefficient {
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.0.to_array()
+    }
+}
+
+impl DeserializableFromArray for CombinationCoefficient {
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Result<Self, ConstructionError> {
+        CurveScalar::from_array(arr).map(Self)
+    }
+}
+
+impl Serialize for CombinationCoefficient {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serde_serialize(self, serializer, Representation::Base64)
+    }
+}
+
+impl<'de> Deserialize<'de> for CombinationCoefficient {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_deserialize(deserializer, Representation::Base64)
+    }
+}
+
+impl HasTypeName for CombinationCoefficient {
+    fn type_name() -> &'static str {
+        "CombinationCoefficient"
+    }
+}
+
+impl fmt::Display for CombinationCoefficient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_public::<Self>(self, f)
+    }
+}
+
+/// Capsule fragments combined by [`combine_cfrags`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CombinedCapsuleFrags {
+    pub(crate) point_e: CurvePoint,
+    pub(crate) point_v: CurvePoint,
+}
+
+type PointSize = <CurvePoint as RepresentableAsArray>::Size;
+
+impl RepresentableAsArray for CombinedCapsuleFrags {
+    type Size = op!(PointSize + PointSize);
+}
+
+impl SerializableToArray for CombinedCapsuleFrags {
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.point_e.to_array().concat(self.point_v.to_array())
+    }
+}
+
+impl DeserializableFromArray for CombinedCapsuleFrags {
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Result<Self, ConstructionError> {
+        let (point_e, rest) = CurvePoint::take(*arr)?;
+        let point_v = CurvePoint::take_last(rest)?;
+        Ok(Self { point_e, point_v })
+    }
+}
+
+impl Serialize for CombinedCapsuleFrags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serde_serialize(self, serializer, Representation::Base64)
+    }
+}
+
+impl<'de> Deserialize<'de> for CombinedCapsuleFrags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_deserialize(deserializer, Representation::Base64)
+    }
+}
+
+impl HasTypeName for CombinedCapsuleFrags {
+    fn type_name() -> &'static str {
+        "CombinedCapsuleFrags"
+    }
+}
+
+impl fmt::Display for CombinedCapsuleFrags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_public::<Self>(self, f)
+    }
+}
+
+/// Calculates the coefficients for combining the given capsule fragments
+/// (one per fragment, in the same order).
+/// This is the part of the combination that requires the receiver's secret key.
+///
+/// The same fragments, in the same order,
+/// must be later passed to [`combine_cfrags`] along with the coefficients.
+pub fn combination_coefficients(
+    receiving_sk: &SecretKey,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+) -> Result<Box<[CombinationCoefficient]>, OpenReencryptedError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    let precursor = capsule::common_precursor(&cfrags)?;
+
+    let pub_key = receiving_sk.public_key().to_point();
+    let dh_point = &precursor * receiving_sk.to_secret_scalar().as_secret();
+
+    let coefficients = capsule::combination_coefficients(&precursor, &pub_key, &dh_point, &cfrags)?;
+    Ok(coefficients
+        .into_iter()
+        .map(CombinationCoefficient)
+        .collect::<Vec<_>>()
+        .into_boxed_slice())
+}
+
+/// Combines the capsule fragments using the coefficients
+/// produced by [`combination_coefficients`].
+/// Does not require any secret data, and therefore can be executed by an untrusted party.
+///
+/// Returns [`OpenReencryptedError::MismatchedCapsuleFrags`] if the fragments are inconsistent,
+/// or the numbers of fragments and coefficients are different.
+pub fn combine_cfrags(
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    coefficients: &[CombinationCoefficient],
+) -> Result<CombinedCapsuleFrags, OpenReencryptedError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    capsule::common_precursor(&cfrags)?;
+
+    if cfrags.len() != coefficients.len() {
+        return Err(OpenReencryptedError::MismatchedCapsuleFrags);
+    }
+
+    let coefficients: Vec<_> = coefficients.iter().map(|coeff| coeff.0).collect();
+    let (point_e, point_v) = capsule::combine_cfrags(&cfrags, &coefficients);
+    Ok(CombinedCapsuleFrags { point_e, point_v })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use rand_core::OsRng;
+
+    use super::{combination_coefficients, combine_cfrags, CombinedCapsuleFrags};
+    use crate::curve::{CurvePoint, CurveScalar};
+    use crate::dem::DEM;
+    use crate::hashing_ds::{hash_capsule_points, hash_to_shared_secret};
+    use crate::serde::tests::{check_deserialization, check_serialization};
+    use crate::serde::Representation;
+    use crate::{
+        decrypt_combined, encrypt, generate_kfrags, reencrypt, DeserializableFromArray,
+        OpenReencryptedError, ReencryptionError, SecretKey, SerializableToArray, Signer,
+    };
+
+    #[test]
+    fn test_offloaded_decryption() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let vcfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag))
+            .collect();
+
+        // On the receiver
+        let coefficients = combination_coefficients(&receiving_sk, &vcfrags).unwrap();
+        assert_eq!(coefficients.len(), vcfrags.len());
+
+        // On the helper
+        let combined = combine_cfrags(&vcfrags, &coefficients).unwrap();
+
+        // Back on the receiver
+        let combined = CombinedCapsuleFrags::from_array(&combined.to_array()).unwrap();
+        let plaintext_back = decrypt_combined(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &vcfrags,
+            &combined,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_back as &[u8], plaintext);
+
+        // Mismatched number of coefficients
+        assert_eq!(
+            combine_cfrags(&vcfrags, &coefficients[0..1]),
+            Err(OpenReencryptedError::MismatchedCapsuleFrags)
+        );
+
+        // Coefficients applied in a wrong order
+        let reversed: Vec<_> = coefficients.iter().rev().cloned().collect();
+        let combined_wrong = combine_cfrags(&vcfrags, &reversed).unwrap();
+        let result = decrypt_combined(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &vcfrags,
+            &combined_wrong,
+            &ciphertext,
+        );
+        assert_eq!(
+            result,
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::ValidationFailed
+            ))
+        );
+
+        // Serde support
+        check_serialization(&combined, Representation::Base64);
+        check_deserialization(&combined);
+        check_serialization(&coefficients[0], Representation::Base64);
+        check_deserialization(&coefficients[0]);
+    }
+
+    #[test]
+    fn test_substituted_precursor() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let (capsule, ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let vcfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag))
+            .collect();
+
+        // The helper picks a precursor it knows the discrete logarithm of,
+        // which lets it calculate the shared secret without the receiver's secret key,
+        // and makes up a combination passing the validation for that precursor.
+        let x = CurveScalar::random_nonzero(&mut OsRng);
+        let precursor = &CurvePoint::generator() * &x;
+        let pub_key = receiving_pk.to_point();
+        let dh_point = &pub_key * &x;
+        let d = hash_to_shared_secret(&precursor, &pub_key, &dh_point);
+        let inv_d: Option<CurveScalar> = d.invert().into();
+        let h = hash_capsule_points(&capsule.point_e, &capsule.point_v);
+        let u = CurveScalar::random_nonzero(&mut OsRng);
+        let forged = CombinedCapsuleFrags {
+            point_e: &delegating_pk.to_point() * &u,
+            point_v: &delegating_pk.to_point()
+                * &(&(&capsule.signature * &inv_d.unwrap()) - &(&h * &u)),
+        };
+
+        // The helper can then encrypt a plaintext of its choice with the resulting key.
+        let key_seed = capsule
+            .open_combined(
+                &delegating_pk,
+                &precursor,
+                &pub_key,
+                &dh_point,
+                &forged.point_e,
+                &forged.point_v,
+            )
+            .unwrap();
+        let forged_ciphertext = DEM::new(&key_seed)
+            .encrypt(&mut OsRng, b"attack at dawn", &capsule.to_array())
+            .unwrap();
+
+        // The receiver takes the precursor from its own fragments, so the forgery is rejected.
+        let result = decrypt_combined(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &vcfrags,
+            &forged,
+            &forged_ciphertext,
+        );
+        assert_eq!(
+            result,
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::ValidationFailed
+            ))
+        );
+
+        // The genuine combination still works.
+        let coefficients = combination_coefficients(&receiving_sk, &vcfrags).unwrap();
+        let combined = combine_cfrags(&vcfrags, &coefficients).unwrap();
+        assert!(decrypt_combined(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &vcfrags,
+            &combined,
+            &ciphertext
+        )
+        .is_ok());
+
+        // No fragments to take the precursor from
+        assert_eq!(
+            decrypt_combined(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &[],
+                &combined,
+                &ciphertext
+            ),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::NoCapsuleFrags
+            ))
+        );
+    }
+}
@@ -23,6 +23,9 @@ use crate::traits::{
 };
 
 pub(crate) type CurveType = Secp256k1;
+pub(crate) const CURVE_NAME: &str = "secp256k1";
+// The crate providing the arithmetic for `CurveType` (a constant-time pure Rust implementation).
+pub(crate) const BACKEND_NAME: &str = "k256";
 
 type BackendScalar = Scalar<CurveType>;
 pub(crate) type BackendNonZeroScalar = NonZeroScalar<CurveType>;
@@ -41,7 +44,7 @@ impl CanBeZeroizedOnDrop for BackendNonZeroScalar {
 //     type PointSize = <Point as RepresentableAsArray>::Size;
 // isn't leaking the `Point` (probably because type aliases are just inlined).
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct CurveScalar(BackendScalar);
 
 impl CurveScalar {
@@ -77,12 +80,6 @@ impl CurveScalar {
     }
 }
 
-impl Default for CurveScalar {
-    fn default() -> Self {
-        Self(BackendScalar::default())
-    }
-}
-
 impl DefaultIsZeroes for CurveScalar {}
 
 impl CanBeZeroizedOnDrop for CurveScalar {
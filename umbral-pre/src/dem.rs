@@ -1,7 +1,9 @@
 use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::fmt;
 
-use aead::{Aead, AeadCore, Payload};
+use aead::{Aead, AeadCore, AeadInPlace, Payload};
 use chacha20poly1305::aead::NewAead;
 use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 use generic_array::{ArrayLength, GenericArray};
@@ -29,6 +31,7 @@ impl fmt::Display for EncryptionError {
 
 /// Errors that can happend during symmetric decryption.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum DecryptionError {
     /// Ciphertext (which should be prepended by the nonce) is shorter than the nonce length.
     CiphertextTooShort,
@@ -38,6 +41,10 @@ pub enum DecryptionError {
     /// - the ciphertext is modified or cut short,
     /// - an incorrect authentication data is provided on decryption.
     AuthenticationFailed,
+    /// The output buffer is too small to fit the plaintext.
+    OutputBufferTooSmall,
+    /// The detached authentication tag has an incorrect size.
+    InvalidTagSize,
 }
 
 impl fmt::Display for DecryptionError {
@@ -50,28 +57,84 @@ impl fmt::Display for DecryptionError {
                 either someone tampered with the ciphertext or \
                 you are using an incorrect decryption key."
             ),
+            Self::OutputBufferTooSmall => {
+                write!(f, "The output buffer is too small to fit the plaintext")
+            }
+            Self::InvalidTagSize => write!(
+                f,
+                "The authentication tag must be {} bytes long",
+                <TagSize as Unsigned>::to_usize()
+            ),
         }
     }
 }
 
+/// Errors that can happen when deriving a key with [`hkdf`].
+#[derive(Debug, PartialEq)]
+pub enum KdfError {
+    /// The requested output is longer than the KDF can produce (255 hash outputs).
+    OutputTooLong,
+}
+
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutputTooLong => write!(f, "Requested KDF output is too long"),
+        }
+    }
+}
+
+pub(crate) const KDF_NAME: &str = "HKDF-SHA256";
+
+fn hkdf_into(
+    ikm: &[u8],
+    salt: Option<&[u8]>,
+    info: Option<&[u8]>,
+    okm: &mut [u8],
+) -> Result<(), KdfError> {
+    let hk = Hkdf::<Sha256>::new(salt, ikm);
+    let def_info = info.unwrap_or(&[]);
+    hk.expand(def_info, okm).or(Err(KdfError::OutputTooLong))
+}
+
 pub(crate) fn kdf<T: AsRef<[u8]> + Clone + CanBeZeroizedOnDrop, S: ArrayLength<u8>>(
     seed: &SecretBox<T>,
     salt: Option<&[u8]>,
     info: Option<&[u8]>,
 ) -> SecretBox<GenericArray<u8, S>> {
-    let hk = Hkdf::<Sha256>::new(salt, seed.as_secret().as_ref());
-
     let mut okm = SecretBox::new(GenericArray::<u8, S>::default());
 
-    let def_info = info.unwrap_or(&[]);
-
     // We can only get an error here if `S` is too large, and it's known at compile-time.
-    hk.expand(def_info, okm.as_mut_secret()).unwrap();
+    hkdf_into(seed.as_secret().as_ref(), salt, info, okm.as_mut_secret()).unwrap();
 
     okm
 }
 
+/// Derives `length` bytes of key material from `ikm` using HKDF-SHA256,
+/// the same KDF that is used internally to derive the DEM keys.
+///
+/// **Warning:** this is an advanced function intended for protocols built on top of Umbral
+/// that need to derive additional keys compatibly with this library.
+/// It is not needed for the regular usage.
+///
+/// `salt` and `info` are optional; a missing `info` is equivalent to an empty one.
+/// The running time depends only on the lengths of the inputs and `length`, not on their values.
+/// Returns [`KdfError::OutputTooLong`] if `length` exceeds 8160 bytes (255 hash outputs).
+pub fn hkdf(
+    ikm: &[u8],
+    salt: Option<&[u8]>,
+    info: Option<&[u8]>,
+    length: usize,
+) -> Result<SecretBox<Vec<u8>>, KdfError> {
+    let mut okm = SecretBox::new(vec![0u8; length]);
+    hkdf_into(ikm, salt, info, okm.as_mut_secret())?;
+    Ok(okm)
+}
+
 type NonceSize = <XChaCha20Poly1305 as AeadCore>::NonceSize;
+type TagSize = <XChaCha20Poly1305 as AeadCore>::TagSize;
+pub(crate) type DemKeySize = <XChaCha20Poly1305 as NewAead>::KeySize;
+pub(crate) const DEM_NAME: &str = "XChaCha20-Poly1305";
 
 impl CanBeZeroizedOnDrop for XChaCha20Poly1305 {
     fn ensure_zeroized_on_drop(&mut self) {
@@ -86,8 +149,7 @@ pub(crate) struct DEM {
 
 impl DEM {
     pub fn new<T: AsRef<[u8]> + Clone + CanBeZeroizedOnDrop>(key_seed: &SecretBox<T>) -> Self {
-        type KeySize = <XChaCha20Poly1305 as NewAead>::KeySize;
-        let key_bytes = kdf::<T, KeySize>(key_seed, None, None);
+        let key_bytes = kdf::<T, DemKeySize>(key_seed, None, None);
         let key = SecretBox::new(*Key::from_slice(key_bytes.as_secret()));
         let cipher = SecretBox::new(XChaCha20Poly1305::new(key.as_secret()));
         Self { cipher }
@@ -120,6 +182,33 @@ impl DEM {
         Ok(result.into_boxed_slice())
     }
 
+    /// Same as [`encrypt`](`Self::encrypt`), but returns the authentication tag
+    /// separately from the rest of the ciphertext (the nonce and the encrypted data).
+    /// Their concatenation is the ciphertext produced by [`encrypt`](`Self::encrypt`).
+    #[allow(clippy::type_complexity)]
+    pub fn encrypt_detached(
+        &self,
+        rng: &mut (impl CryptoRng + RngCore),
+        data: &[u8],
+        authenticated_data: &[u8],
+    ) -> Result<(Box<[u8]>, Box<[u8]>), EncryptionError> {
+        let nonce_size = <NonceSize as Unsigned>::to_usize();
+        let mut nonce = GenericArray::<u8, NonceSize>::default();
+        rng.fill_bytes(&mut nonce);
+        let nonce = XNonce::from_slice(&nonce);
+
+        let mut body = Vec::<u8>::with_capacity(nonce_size + data.len());
+        body.extend_from_slice(nonce);
+        body.extend_from_slice(data);
+        let tag = self
+            .cipher
+            .as_secret()
+            .encrypt_in_place_detached(nonce, authenticated_data, &mut body[nonce_size..])
+            .or(Err(EncryptionError::PlaintextTooLarge))?;
+
+        Ok((body.into_boxed_slice(), tag.to_vec().into_boxed_slice()))
+    }
+
     pub fn decrypt(
         &self,
         ciphertext: impl AsRef<[u8]>,
@@ -143,6 +232,86 @@ impl DEM {
             .map(|pt| pt.into_boxed_slice())
             .or(Err(DecryptionError::AuthenticationFailed))
     }
+
+    /// Decrypts the ciphertext produced by [`encrypt_detached`](`Self::encrypt_detached`)
+    /// given the detached authentication tag.
+    pub fn decrypt_detached(
+        &self,
+        ciphertext_body: impl AsRef<[u8]>,
+        tag: &[u8],
+        authenticated_data: &[u8],
+    ) -> Result<Box<[u8]>, DecryptionError> {
+        let ciphertext_body = ciphertext_body.as_ref();
+        let nonce_size = <NonceSize as Unsigned>::to_usize();
+
+        if ciphertext_body.len() < nonce_size {
+            return Err(DecryptionError::CiphertextTooShort);
+        }
+        if tag.len() != <TagSize as Unsigned>::to_usize() {
+            return Err(DecryptionError::InvalidTagSize);
+        }
+
+        let nonce = XNonce::from_slice(&ciphertext_body[..nonce_size]);
+        let mut plaintext = ciphertext_body[nonce_size..].to_vec();
+        self.cipher
+            .as_secret()
+            .decrypt_in_place_detached(
+                nonce,
+                authenticated_data,
+                &mut plaintext,
+                GenericArray::from_slice(tag),
+            )
+            .or(Err(DecryptionError::AuthenticationFailed))?;
+        Ok(plaintext.into_boxed_slice())
+    }
+
+    /// Returns the size of the plaintext encrypted in a ciphertext of the given size,
+    /// or `None` if the ciphertext is too short to be valid.
+    pub fn plaintext_size(ciphertext_size: usize) -> Option<usize> {
+        let overhead = <NonceSize as Unsigned>::to_usize() + <TagSize as Unsigned>::to_usize();
+        ciphertext_size.checked_sub(overhead)
+    }
+
+    /// Same as [`decrypt`](`Self::decrypt`), but writes the plaintext
+    /// into the beginning of `out` and returns its size.
+    pub fn decrypt_into(
+        &self,
+        ciphertext: impl AsRef<[u8]>,
+        authenticated_data: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize, DecryptionError> {
+        let ciphertext = ciphertext.as_ref();
+        let nonce_size = <NonceSize as Unsigned>::to_usize();
+
+        if ciphertext.len() < nonce_size {
+            return Err(DecryptionError::CiphertextTooShort);
+        }
+        // A ciphertext shorter than the tag cannot be authenticated,
+        // which is what `decrypt()` reports in this case too.
+        let plaintext_size =
+            Self::plaintext_size(ciphertext.len()).ok_or(DecryptionError::AuthenticationFailed)?;
+        if out.len() < plaintext_size {
+            return Err(DecryptionError::OutputBufferTooSmall);
+        }
+
+        let nonce = XNonce::from_slice(&ciphertext[..nonce_size]);
+        let (msg, tag) = ciphertext[nonce_size..].split_at(plaintext_size);
+        let buffer = &mut out[..plaintext_size];
+        buffer.copy_from_slice(msg);
+
+        // The tag is checked before decryption,
+        // so on failure the buffer only contains the ciphertext.
+        self.cipher
+            .as_secret()
+            .decrypt_in_place_detached(
+                nonce,
+                authenticated_data,
+                buffer,
+                GenericArray::from_slice(tag),
+            )
+            .or(Err(DecryptionError::AuthenticationFailed))?;
+        Ok(plaintext_size)
+    }
 }
 
 #[cfg(test)]
@@ -151,7 +320,7 @@ mod tests {
     use generic_array::GenericArray;
     use typenum::U32;
 
-    use super::kdf;
+    use super::{hkdf, kdf, KdfError};
     use crate::curve::CurvePoint;
     use crate::secret_box::SecretBox;
     use crate::{RepresentableAsArray, SerializableToArray};
@@ -169,5 +338,40 @@ mod tests {
 
         let key_diff = kdf::<PointArray, U32>(&key_box, None, Some(&info[..]));
         assert_ne!(key.as_secret(), key_diff.as_secret());
+
+        // The public helper produces the same output
+        let key_public = hkdf(&p1.to_array(), Some(&salt[..]), Some(&info[..]), 32).unwrap();
+        assert_eq!(
+            key_public.as_secret().as_slice(),
+            key.as_secret().as_slice()
+        );
+    }
+
+    #[test]
+    fn test_hkdf_vectors() {
+        // Test cases 1 and 3 from RFC 5869
+        let ikm = [0x0bu8; 22];
+        let salt: [u8; 13] = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+        ];
+        let info: [u8; 10] = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+
+        let okm = hkdf(&ikm, Some(&salt), Some(&info), 42).unwrap();
+        assert_eq!(
+            hex::encode(okm.as_secret()),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+
+        let okm = hkdf(&ikm, None, None, 42).unwrap();
+        assert_eq!(
+            hex::encode(okm.as_secret()),
+            "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+        );
+
+        assert!(hkdf(&ikm, None, None, 255 * 32).is_ok());
+        assert_eq!(
+            hkdf(&ikm, None, None, 255 * 32 + 1).map(|okm| okm.as_secret().len()),
+            Err(KdfError::OutputTooLong)
+        );
     }
 }
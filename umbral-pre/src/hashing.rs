@@ -31,9 +31,9 @@ pub fn unsafe_hash_to_point(dst: &[u8], data: &[u8]) -> Option<CurvePoint> {
     let mut i = 0u32;
     while i < <u32>::MAX {
         let result = BytesDigest::new_with_dst(dst)
-            .chain_bytes(&data_len)
+            .chain_bytes(data_len)
             .chain_bytes(data)
-            .chain_bytes(&i.to_be_bytes())
+            .chain_bytes(i.to_be_bytes())
             .finalize();
 
         // Set the sign byte
@@ -54,6 +54,7 @@ pub fn unsafe_hash_to_point(dst: &[u8], data: &[u8]) -> Option<CurvePoint> {
 
 // Our hash of choice.
 pub(crate) type BackendDigest = Sha256;
+pub(crate) const BACKEND_DIGEST_NAME: &str = "SHA-256";
 
 // Wraps BackendDigest for easier replacement, and standardizes the use of DST.
 pub(crate) struct Hash(BackendDigest);
@@ -107,7 +108,7 @@ impl ScalarDigest {
     }
 
     pub fn chain_point(self, point: &CurvePoint) -> Self {
-        self.chain_bytes(&point.to_array())
+        self.chain_bytes(point.to_array())
     }
 
     pub fn chain_points(self, points: &[CurvePoint]) -> Self {
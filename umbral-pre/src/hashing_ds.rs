@@ -4,11 +4,13 @@
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 
+use generic_array::GenericArray;
+
 use crate::curve::{CurvePoint, CurveScalar};
-use crate::hashing::ScalarDigest;
-use crate::key_frag::KeyFragID;
-use crate::keys::PublicKey;
-use crate::traits::SerializableToArray;
+use crate::hashing::{BytesDigest, HashOutputSize, ScalarDigest};
+use crate::key_frag::{KeyFragID, ReceiverTag};
+use crate::keys::{PublicKey, SecretKey};
+use crate::traits::{SerializableToArray, SerializableToSecretArray};
 
 // TODO (#39): Ideally this should return a non-zero scalar.
 pub(crate) fn hash_to_polynomial_arg(
@@ -50,18 +52,69 @@ pub(crate) fn hash_to_cfrag_verification(points: &[CurvePoint]) -> CurveScalar {
         .finalize()
 }
 
+pub(crate) fn hash_to_receiver_tag(
+    precursor: &CurvePoint,
+    pubkey: &CurvePoint,
+    dh_point: &CurvePoint,
+) -> GenericArray<u8, HashOutputSize> {
+    BytesDigest::new_with_dst(b"RECEIVER_TAG")
+        .chain_bytes(precursor.to_array())
+        .chain_bytes(pubkey.to_array())
+        .chain_bytes(dh_point.to_array())
+        .finalize()
+}
+
+pub(crate) fn hash_to_kfrag_id(seed: &[u8], label: &[u8]) -> GenericArray<u8, HashOutputSize> {
+    let seed_len = (seed.len() as u32).to_be_bytes();
+    BytesDigest::new_with_dst(b"KFRAG_ID")
+        .chain_bytes(seed_len)
+        .chain_bytes(seed)
+        .chain_bytes(label)
+        .finalize()
+}
+
+pub(crate) fn hash_to_delegation_scalar(
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    threshold: usize,
+    seed: &[u8],
+    counter: u32,
+) -> CurveScalar {
+    let seed_len = (seed.len() as u32).to_be_bytes();
+    ScalarDigest::new_with_dst(b"DELEGATION_SCALAR")
+        .chain_secret_bytes(&delegating_sk.to_secret_array())
+        .chain_bytes(receiving_pk.to_array())
+        .chain_bytes((threshold as u64).to_be_bytes())
+        .chain_bytes(seed_len)
+        .chain_bytes(seed)
+        .chain_bytes(counter.to_be_bytes())
+        .finalize()
+}
+
 pub(crate) fn kfrag_signature_message(
     kfrag_id: &KeyFragID,
     commitment: &CurvePoint,
     precursor: &CurvePoint,
+    receiver_tag: &ReceiverTag,
     maybe_delegating_pk: Option<&PublicKey>,
     maybe_receiving_pk: Option<&PublicKey>,
 ) -> Box<[u8]> {
-    let mut result = Vec::<u8>::new();
+    kfrag_signature_message_with_keys(
+        kfrag_id,
+        commitment,
+        precursor,
+        receiver_tag,
+        &kfrag_signature_message_keys(maybe_delegating_pk, maybe_receiving_pk),
+    )
+}
 
-    result.extend_from_slice(&kfrag_id.to_array());
-    result.extend_from_slice(&commitment.to_array());
-    result.extend_from_slice(&precursor.to_array());
+/// The part of the kfrag signature message that only depends on the keys,
+/// and can be reused for multiple kfrags of the same delegation.
+pub(crate) fn kfrag_signature_message_keys(
+    maybe_delegating_pk: Option<&PublicKey>,
+    maybe_receiving_pk: Option<&PublicKey>,
+) -> Box<[u8]> {
+    let mut result = Vec::<u8>::new();
 
     match maybe_delegating_pk {
         Some(delegating_pk) => {
@@ -81,3 +134,21 @@ pub(crate) fn kfrag_signature_message(
 
     result.into_boxed_slice()
 }
+
+pub(crate) fn kfrag_signature_message_with_keys(
+    kfrag_id: &KeyFragID,
+    commitment: &CurvePoint,
+    precursor: &CurvePoint,
+    receiver_tag: &ReceiverTag,
+    keys_message: &[u8],
+) -> Box<[u8]> {
+    let mut result = Vec::<u8>::new();
+
+    result.extend_from_slice(&kfrag_id.to_array());
+    result.extend_from_slice(&commitment.to_array());
+    result.extend_from_slice(&precursor.to_array());
+    result.extend_from_slice(&receiver_tag.to_array());
+    result.extend_from_slice(keys_message);
+
+    result.into_boxed_slice()
+}
@@ -0,0 +1,132 @@
+//! Using Umbral as a KEM only, with an external DEM.
+//!
+//! [`derive_symmetric_key`] creates a capsule along with a symmetric key,
+//! which can be used with any AEAD (e.g. AES-GCM) instead of the built-in DEM
+//! of [`encrypt`](`crate::encrypt`).
+//! The owner of the delegating key recovers the key with [`open_symmetric_key`],
+//! and the receiver of a delegation with [`open_reencrypted_symmetric_key`],
+//! given the capsule fragments obtained by reencrypting the capsule as usual.
+//!
+//! The key is derived from the capsule's shared secret with a separate domain separation tag,
+//! so it differs from the key the built-in DEM would use for the same capsule.
+//! The key is secret, and it is the caller's responsibility to use it correctly
+//! (in particular, to never reuse a nonce with the same key).
+
+use alloc::vec::Vec;
+
+use generic_array::GenericArray;
+use rand_core::{CryptoRng, RngCore};
+use typenum::U32;
+
+#[cfg(feature = "default-rng")]
+use rand_core::OsRng;
+
+use crate::capsule::{Capsule, KeySeed, OpenReencryptedError};
+use crate::capsule_frag::VerifiedCapsuleFrag;
+use crate::dem::kdf;
+use crate::keys::{PublicKey, SecretKey};
+use crate::secret_box::SecretBox;
+
+/// The size of the symmetric key produced by [`derive_symmetric_key`], in bytes.
+pub type SymmetricKeySize = U32;
+
+fn symmetric_key(key_seed: &SecretBox<KeySeed>) -> SecretBox<GenericArray<u8, SymmetricKeySize>> {
+    kdf::<KeySeed, SymmetricKeySize>(key_seed, None, Some(b"SYMMETRIC_KEY"))
+}
+
+/// Generates a symmetric key for an external DEM, using the given RNG.
+/// Returns the KEM [`Capsule`] and the key.
+pub fn derive_symmetric_key_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_pk: &PublicKey,
+) -> (Capsule, SecretBox<GenericArray<u8, SymmetricKeySize>>) {
+    let (capsule, key_seed) = Capsule::from_public_key(rng, delegating_pk);
+    (capsule, symmetric_key(&key_seed))
+}
+
+/// A synonym for [`derive_symmetric_key_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+pub fn derive_symmetric_key(
+    delegating_pk: &PublicKey,
+) -> (Capsule, SecretBox<GenericArray<u8, SymmetricKeySize>>) {
+    derive_symmetric_key_with_rng(&mut OsRng, delegating_pk)
+}
+
+/// Recovers the symmetric key created by [`derive_symmetric_key`]
+/// using the delegator's secret key.
+///
+/// Since the KEM is not authenticated by itself, an incorrect key does not result in an error,
+/// but in a different symmetric key, which will be rejected by the external AEAD.
+pub fn open_symmetric_key(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+) -> SecretBox<GenericArray<u8, SymmetricKeySize>> {
+    symmetric_key(&capsule.open_original(delegating_sk))
+}
+
+/// Recovers the symmetric key created by [`derive_symmetric_key`]
+/// using previously reencrypted capsule fragments.
+///
+/// See [`decrypt_reencrypted`](`crate::decrypt_reencrypted`) for the requirements
+/// on the capsule fragments.
+pub fn open_reencrypted_symmetric_key(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+) -> Result<SecretBox<GenericArray<u8, SymmetricKeySize>>, OpenReencryptedError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    capsule
+        .open_reencrypted(receiving_sk, delegating_pk, &cfrags)
+        .map(|key_seed| symmetric_key(&key_seed))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::{derive_symmetric_key, open_reencrypted_symmetric_key, open_symmetric_key};
+    use crate::dem::kdf;
+    use crate::{generate_kfrags, reencrypt, SecretKey, Signer};
+
+    #[test]
+    fn test_symmetric_key() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let (capsule, key) = derive_symmetric_key(&delegating_pk);
+
+        let key_alice = open_symmetric_key(&delegating_sk, &capsule);
+        assert_eq!(key_alice.as_secret(), key.as_secret());
+
+        // A different key results in a different symmetric key
+        let key_other = open_symmetric_key(&SecretKey::random(), &capsule);
+        assert_ne!(key_other.as_secret(), key.as_secret());
+
+        // The key is not the one the built-in DEM would use
+        let dem_key =
+            kdf::<_, super::SymmetricKeySize>(&capsule.open_original(&delegating_sk), None, None);
+        assert_ne!(dem_key.as_secret(), key.as_secret());
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let vcfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag))
+            .collect();
+
+        let key_bob =
+            open_reencrypted_symmetric_key(&receiving_sk, &delegating_pk, &capsule, &vcfrags)
+                .unwrap();
+        assert_eq!(key_bob.as_secret(), key.as_secret());
+    }
+}
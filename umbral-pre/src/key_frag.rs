@@ -9,8 +9,13 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use typenum::{op, U32};
 
 use crate::curve::{CurvePoint, CurveScalar};
-use crate::hashing_ds::{hash_to_polynomial_arg, hash_to_shared_secret, kfrag_signature_message};
+use crate::hashing::HashOutputSize;
+use crate::hashing_ds::{
+    hash_to_delegation_scalar, hash_to_kfrag_id, hash_to_polynomial_arg, hash_to_receiver_tag,
+    hash_to_shared_secret, kfrag_signature_message,
+};
 use crate::keys::{PublicKey, SecretKey, Signature, Signer};
+use crate::metrics::{record, Operation};
 use crate::params::Parameters;
 use crate::serde::{serde_deserialize, serde_serialize, Representation};
 use crate::traits::{
@@ -31,6 +36,10 @@ impl KeyFragID {
         rng.fill_bytes(&mut bytes);
         Self(bytes)
     }
+
+    pub(crate) fn from_label(seed: &[u8], label: &[u8]) -> Self {
+        Self(hash_to_kfrag_id(seed, label))
+    }
 }
 
 impl AsRef<[u8]> for KeyFragID {
@@ -55,6 +64,44 @@ impl DeserializableFromArray for KeyFragID {
     }
 }
 
+/// A tag identifying the receiver of a delegation, derived from the shared secret
+/// of the delegation's precursor and the receiving key.
+/// Allows the receiver to detect that the fragments were generated for a different key,
+/// while not revealing the receiving key to anyone else.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ReceiverTag(GenericArray<u8, ReceiverTagSize>);
+
+pub(crate) type ReceiverTagSize = HashOutputSize;
+
+impl ReceiverTag {
+    pub(crate) fn new(precursor: &CurvePoint, pubkey: &CurvePoint, dh_point: &CurvePoint) -> Self {
+        Self(hash_to_receiver_tag(precursor, pubkey, dh_point))
+    }
+
+    /// Calculates the tag the receiver expects for fragments with the given precursor.
+    pub(crate) fn for_receiver(precursor: &CurvePoint, receiving_sk: &SecretKey) -> Self {
+        let pubkey = receiving_sk.public_key().to_point();
+        let dh_point = precursor * receiving_sk.to_secret_scalar().as_secret();
+        Self::new(precursor, &pubkey, &dh_point)
+    }
+}
+
+impl RepresentableAsArray for ReceiverTag {
+    type Size = ReceiverTagSize;
+}
+
+impl SerializableToArray for ReceiverTag {
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.0
+    }
+}
+
+impl DeserializableFromArray for ReceiverTag {
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Result<Self, ConstructionError> {
+        Ok(Self(*arr))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct KeyFragProof {
     pub(crate) commitment: CurvePoint,
@@ -130,6 +177,7 @@ impl KeyFragProof {
                 kfrag_id,
                 &commitment,
                 &base.precursor,
+                &base.receiver_tag,
                 maybe_delegating_pk,
                 maybe_receiving_pk,
             )
@@ -142,6 +190,7 @@ impl KeyFragProof {
                 kfrag_id,
                 &commitment,
                 &base.precursor,
+                &base.receiver_tag,
                 none_unless(maybe_delegating_pk, sign_delegating_key),
                 none_unless(maybe_receiving_pk, sign_receiving_key),
             )
@@ -163,17 +212,29 @@ impl KeyFragProof {
 }
 
 /// A fragment of the encrypting party's key used to create a [`CapsuleFrag`](`crate::CapsuleFrag`).
+///
+/// The signature of the delegating party covers the fragment ID, the commitment to the key,
+/// the precursor, the receiver tag, and, if requested in
+/// [`generate_kfrags()`](`crate::generate_kfrags()`), the delegating and the receiving keys
+/// (along with the flags showing whether they were included).
+/// The key itself is protected through the commitment, which is checked against it.
+/// Therefore a modification of any part of the fragment is detected by [`KeyFrag::verify`].
+///
+/// The threshold is not stored in the fragment explicitly;
+/// it is the number of coefficients of the polynomial the fragment key was evaluated from,
+/// and cannot be changed without invalidating all the fragments of the delegation.
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyFrag {
     params: Parameters,
     pub(crate) id: KeyFragID,
     pub(crate) key: CurveScalar,
     pub(crate) precursor: CurvePoint,
+    pub(crate) receiver_tag: ReceiverTag,
     pub(crate) proof: KeyFragProof,
 }
 
 impl RepresentableAsArray for KeyFrag {
-    type Size = op!(ScalarSize + ScalarSize + PointSize + KeyFragProofSize);
+    type Size = op!(ScalarSize + ScalarSize + PointSize + ReceiverTagSize + KeyFragProofSize);
 }
 
 impl SerializableToArray for KeyFrag {
@@ -182,6 +243,7 @@ impl SerializableToArray for KeyFrag {
             .to_array()
             .concat(self.key.to_array())
             .concat(self.precursor.to_array())
+            .concat(self.receiver_tag.to_array())
             .concat(self.proof.to_array())
     }
 }
@@ -192,12 +254,14 @@ impl DeserializableFromArray for KeyFrag {
         let (id, rest) = KeyFragID::take(*arr)?;
         let (key, rest) = CurveScalar::take(rest)?;
         let (precursor, rest) = CurvePoint::take(rest)?;
+        let (receiver_tag, rest) = ReceiverTag::take(rest)?;
         let proof = KeyFragProof::take_last(rest)?;
         Ok(Self {
             params,
             id,
             key,
             precursor,
+            receiver_tag,
             proof,
         })
     }
@@ -233,6 +297,61 @@ impl fmt::Display for KeyFrag {
     }
 }
 
+/// A commitment to the key of a [`KeyFrag`], returned by [`KeyFrag::commitment`].
+///
+/// Can be published by the holder of the key fragment,
+/// allowing anyone to check the capsule fragments it produces
+/// with [`CapsuleFrag::verify_against_commitment`](`crate::CapsuleFrag::verify_against_commitment`)
+/// without having access to the key fragment itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyFragCommitment(pub(crate) CurvePoint);
+
+impl RepresentableAsArray for KeyFragCommitment {
+    type Size = PointSize;
+}
+
+impl SerializableToArray for KeyFragCommitment {
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.0.to_array()
+    }
+}
+
+impl DeserializableFromArray for KeyFragCommitment {
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Result<Self, ConstructionError> {
+        CurvePoint::from_array(arr).map(Self)
+    }
+}
+
+impl Serialize for KeyFragCommitment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serde_serialize(self, serializer, Representation::Base64)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyFragCommitment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_deserialize(deserializer, Representation::Base64)
+    }
+}
+
+impl HasTypeName for KeyFragCommitment {
+    fn type_name() -> &'static str {
+        "KeyFragCommitment"
+    }
+}
+
+impl fmt::Display for KeyFragCommitment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_public::<Self>(self, f)
+    }
+}
+
 /// Possible errors that can be returned by [`KeyFrag::verify`].
 #[derive(Debug, PartialEq)]
 pub enum KeyFragVerificationError {
@@ -260,14 +379,18 @@ impl fmt::Display for KeyFragVerificationError {
 }
 
 impl KeyFrag {
+    /// Returns the commitment to the key of this fragment.
+    pub fn commitment(&self) -> KeyFragCommitment {
+        KeyFragCommitment(self.proof.commitment)
+    }
+
     fn from_base(
         rng: &mut (impl CryptoRng + RngCore),
         base: &KeyFragBase,
+        kfrag_id: KeyFragID,
         sign_delegating_key: bool,
         sign_receiving_key: bool,
     ) -> Self {
-        let kfrag_id = KeyFragID::random(rng);
-
         // The index of the re-encryption key share (which in Shamir's Secret
         // Sharing corresponds to x in the tuple (x, f(x)), with f being the
         // generating polynomial), is used to prevent reconstruction of the
@@ -297,6 +420,7 @@ impl KeyFrag {
             id: kfrag_id,
             key: rk,
             precursor: base.precursor,
+            receiver_tag: base.receiver_tag,
             proof,
         }
     }
@@ -312,6 +436,18 @@ impl KeyFrag {
         verifying_pk: &PublicKey,
         maybe_delegating_pk: Option<&PublicKey>,
         maybe_receiving_pk: Option<&PublicKey>,
+    ) -> Result<VerifiedKeyFrag, KeyFragVerificationError> {
+        record(
+            Operation::Verify,
+            self.verify_impl(verifying_pk, maybe_delegating_pk, maybe_receiving_pk),
+        )
+    }
+
+    pub(crate) fn verify_impl(
+        &self,
+        verifying_pk: &PublicKey,
+        maybe_delegating_pk: Option<&PublicKey>,
+        maybe_receiving_pk: Option<&PublicKey>,
     ) -> Result<VerifiedKeyFrag, KeyFragVerificationError> {
         let u = self.params.u;
 
@@ -343,6 +479,7 @@ impl KeyFrag {
                 &kfrag_id,
                 &commitment,
                 &precursor,
+                &self.receiver_tag,
                 none_unless(maybe_delegating_pk, self.proof.delegating_key_signed),
                 none_unless(maybe_receiving_pk, self.proof.receiving_key_signed),
             )
@@ -393,12 +530,28 @@ impl VerifiedKeyFrag {
         base: &KeyFragBase,
         sign_delegating_key: bool,
         sign_receiving_key: bool,
+    ) -> Self {
+        let kfrag_id = KeyFragID::random(rng);
+        Self::from_base_with_id(rng, base, kfrag_id, sign_delegating_key, sign_receiving_key)
+    }
+
+    pub(crate) fn from_base_with_id(
+        rng: &mut (impl CryptoRng + RngCore),
+        base: &KeyFragBase,
+        kfrag_id: KeyFragID,
+        sign_delegating_key: bool,
+        sign_receiving_key: bool,
     ) -> Self {
         Self {
-            kfrag: KeyFrag::from_base(rng, base, sign_delegating_key, sign_receiving_key),
+            kfrag: KeyFrag::from_base(rng, base, kfrag_id, sign_delegating_key, sign_receiving_key),
         }
     }
 
+    /// Returns the commitment to the key of this fragment.
+    pub fn commitment(&self) -> KeyFragCommitment {
+        self.kfrag.commitment()
+    }
+
     /// Restores a verified keyfrag directly from serialized bytes,
     /// skipping [`KeyFrag::verify`] call.
     ///
@@ -409,10 +562,113 @@ impl VerifiedKeyFrag {
     }
 }
 
+/// Possible errors that can be returned by [`check_kfrags_consistency`].
+#[derive(Debug, PartialEq)]
+pub enum KeyFragSetError {
+    /// An empty key fragment list is given.
+    NoKeyFrags,
+    /// Key fragments originate from different delegations
+    /// (different [`generate_kfrags`](crate::generate_kfrags) calls).
+    MismatchedDelegations,
+    /// Some of the given key fragments are repeated.
+    RepeatingKeyFrags,
+}
+
+impl fmt::Display for KeyFragSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoKeyFrags => write!(f, "Empty KeyFrag sequence"),
+            Self::MismatchedDelegations => {
+                write!(f, "KeyFrags originate from different delegations")
+            }
+            Self::RepeatingKeyFrags => write!(f, "Some of the KeyFrags are repeated"),
+        }
+    }
+}
+
+/// Returns `true` if all the given key fragments belong to the same delegation
+/// (or if there are none).
+fn same_delegation(kfrags: &[VerifiedKeyFrag]) -> bool {
+    // All the fragments of a delegation share the same precursor, and different delegations
+    // have different ones (it is either random, or derived from the delegation seed).
+    match kfrags.first() {
+        None => true,
+        Some(first) => kfrags
+            .iter()
+            .all(|vkfrag| vkfrag.kfrag.precursor == first.kfrag.precursor),
+    }
+}
+
+/// Checks that the given key fragments are distinct and belong to the same delegation
+/// (that is, were produced by the same [`generate_kfrags`](crate::generate_kfrags) call).
+///
+/// The threshold is not stored in the key fragments,
+/// but it is fixed for a delegation, so fragments passing this check
+/// are guaranteed to share the same threshold.
+/// Fragments mixed from delegations with different thresholds will fail the check.
+pub fn check_kfrags_consistency(kfrags: &[VerifiedKeyFrag]) -> Result<(), KeyFragSetError> {
+    if kfrags.is_empty() {
+        return Err(KeyFragSetError::NoKeyFrags);
+    }
+
+    if !same_delegation(kfrags) {
+        return Err(KeyFragSetError::MismatchedDelegations);
+    }
+
+    for (i, vkfrag) in kfrags.iter().enumerate() {
+        if kfrags[i + 1..]
+            .iter()
+            .any(|other| other.kfrag.id == vkfrag.kfrag.id)
+        {
+            return Err(KeyFragSetError::RepeatingKeyFrags);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether two sets of key fragments represent the same delegation,
+/// that is, whether they originate from the same [`generate_kfrags`](crate::generate_kfrags)
+/// call and contain the same fragments, regardless of their order.
+///
+/// Repeated fragments are ignored.
+/// A set containing fragments from several delegations is not equal to any other set.
+/// Two empty sets are considered equal.
+///
+/// Fragments regenerated by [`generate_deterministic_kfrags`](crate::generate_deterministic_kfrags)
+/// with the same keys, threshold and seed belong to the same delegation as the original ones,
+/// so they compare equal to them (the signatures are not compared).
+pub fn delegations_equal(kfrags_a: &[VerifiedKeyFrag], kfrags_b: &[VerifiedKeyFrag]) -> bool {
+    let (first_a, first_b) = match (kfrags_a.first(), kfrags_b.first()) {
+        (None, None) => return true,
+        (Some(first_a), Some(first_b)) => (first_a, first_b),
+        _ => return false,
+    };
+
+    if !same_delegation(kfrags_a)
+        || !same_delegation(kfrags_b)
+        || first_a.kfrag.precursor != first_b.kfrag.precursor
+    {
+        return false;
+    }
+
+    let contains = |kfrags: &[VerifiedKeyFrag], id: &KeyFragID| {
+        kfrags.iter().any(|vkfrag| &vkfrag.kfrag.id == id)
+    };
+
+    kfrags_a
+        .iter()
+        .all(|vkfrag| contains(kfrags_b, &vkfrag.kfrag.id))
+        && kfrags_b
+            .iter()
+            .all(|vkfrag| contains(kfrags_a, &vkfrag.kfrag.id))
+}
+
 pub(crate) struct KeyFragBase {
     signer: Signer,
     precursor: CurvePoint,
     dh_point: CurvePoint,
+    receiver_tag: ReceiverTag,
     params: Parameters,
     delegating_pk: PublicKey,
     receiving_pk: PublicKey,
@@ -426,6 +682,45 @@ impl KeyFragBase {
         receiving_pk: &PublicKey,
         signer: &Signer,
         threshold: usize,
+    ) -> Self {
+        Self::from_scalars(delegating_sk, receiving_pk, signer, threshold, || {
+            CurveScalar::random_nonzero(rng)
+        })
+    }
+
+    /// Creates a base whose secret values (the private precursor and the polynomial coefficients)
+    /// are derived from `delegating_sk`, `receiving_pk`, `threshold` and `delegation_seed`,
+    /// so that the same inputs always result in the same delegation.
+    pub fn new_deterministic(
+        delegating_sk: &SecretKey,
+        receiving_pk: &PublicKey,
+        signer: &Signer,
+        threshold: usize,
+        delegation_seed: &[u8],
+    ) -> Self {
+        let mut counter = 0u32;
+        Self::from_scalars(delegating_sk, receiving_pk, signer, threshold, || loop {
+            let scalar = hash_to_delegation_scalar(
+                delegating_sk,
+                receiving_pk,
+                threshold,
+                delegation_seed,
+                counter,
+            );
+            counter += 1;
+            if !scalar.is_zero() {
+                break scalar;
+            }
+        })
+    }
+
+    /// `next_scalar` is the source of the secret values, and must return non-zero scalars.
+    fn from_scalars(
+        delegating_sk: &SecretKey,
+        receiving_pk: &PublicKey,
+        signer: &Signer,
+        threshold: usize,
+        mut next_scalar: impl FnMut() -> CurveScalar,
     ) -> Self {
         let g = CurvePoint::generator();
         let params = Parameters::new();
@@ -437,7 +732,7 @@ impl KeyFragBase {
         let (d, precursor, dh_point) = loop {
             // The precursor point is used as an ephemeral public key in a DH key exchange,
             // and the resulting shared secret 'dh_point' is used to derive other secret values
-            let private_precursor = CurveScalar::random_nonzero(rng);
+            let private_precursor = next_scalar();
             let precursor = &g * &private_precursor;
 
             let dh_point = &receiving_pk_point * &private_precursor;
@@ -459,13 +754,16 @@ impl KeyFragBase {
         let mut coefficients = Vec::<CurveScalar>::with_capacity(threshold);
         coefficients.push(coefficient0);
         for _i in 1..threshold {
-            coefficients.push(CurveScalar::random_nonzero(rng));
+            coefficients.push(next_scalar());
         }
 
+        let receiver_tag = ReceiverTag::new(&precursor, &receiving_pk_point, &dh_point);
+
         Self {
             signer: signer.clone(),
             precursor,
             dh_point,
+            receiver_tag,
             params,
             delegating_pk,
             receiving_pk: *receiving_pk,
@@ -487,13 +785,21 @@ fn poly_eval(coeffs: &[CurveScalar], x: &CurveScalar) -> CurveScalar {
 mod tests {
 
     use alloc::boxed::Box;
+    use alloc::vec::Vec;
 
     use rand_core::OsRng;
 
-    use super::{KeyFrag, KeyFragBase, KeyFragVerificationError, VerifiedKeyFrag};
+    use super::{
+        check_kfrags_consistency, delegations_equal, KeyFrag, KeyFragBase, KeyFragID,
+        KeyFragSetError, KeyFragVerificationError, ReceiverTag, VerifiedKeyFrag,
+    };
+    use crate::curve::{CurvePoint, CurveScalar};
     use crate::serde::tests::{check_deserialization, check_serialization};
     use crate::serde::Representation;
-    use crate::{DeserializableFromArray, PublicKey, SecretKey, SerializableToArray, Signer};
+    use crate::{
+        generate_kfrags, DeserializableFromArray, PublicKey, RepresentableAsArray, SecretKey,
+        SerializableToArray, Signature, Signer,
+    };
 
     fn prepare_kfrags(
         sign_delegating_key: bool,
@@ -562,6 +868,138 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_tampered() {
+        let (delegating_pk, receiving_pk, verifying_pk, vkfrags) = prepare_kfrags(true, true);
+        let kfrag_arr = vkfrags[0].to_array();
+
+        let id_size = KeyFragID::serialized_size();
+        let scalar_size = CurveScalar::serialized_size();
+        let point_size = CurvePoint::serialized_size();
+        let tag_size = ReceiverTag::serialized_size();
+        let signature_size = Signature::serialized_size();
+
+        let id_offset = 0;
+        let key_offset = id_offset + id_size;
+        let precursor_offset = key_offset + scalar_size;
+        let tag_offset = precursor_offset + point_size;
+        let commitment_offset = tag_offset + tag_size;
+        let proxy_signature_offset = commitment_offset + point_size;
+        let receiver_signature_offset = proxy_signature_offset + signature_size;
+        let dk_flag_offset = receiver_signature_offset + signature_size;
+        let rk_flag_offset = dk_flag_offset + 1;
+        assert_eq!(rk_flag_offset + 1, KeyFrag::serialized_size());
+
+        #[derive(Debug)]
+        enum Outcome {
+            DeserializationFails,
+            VerificationFails(KeyFragVerificationError),
+            // The signature for the receiver is not checked by `KeyFrag::verify()`,
+            // it is carried over to the capsule fragments and checked there.
+            VerificationSucceeds,
+        }
+
+        // The bytes are chosen so that the outcome does not depend on the random keys:
+        // flipping the lowest bit of the compressed point prefix negates the point,
+        // flipping the lowest bit of the last byte of a scalar keeps it in range
+        // (barring a negligible probability), and setting a higher bit of the prefix
+        // or of a flag makes an invalid encoding.
+        let tampered_cases = [
+            (
+                id_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (
+                key_offset + scalar_size - 1,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectCommitment),
+            ),
+            (
+                precursor_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (precursor_offset, 8, Outcome::DeserializationFails),
+            (
+                tag_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (
+                commitment_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectCommitment),
+            ),
+            (commitment_offset, 8, Outcome::DeserializationFails),
+            // The last bytes of `r` and `s`
+            (
+                proxy_signature_offset + scalar_size - 1,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (
+                proxy_signature_offset + signature_size - 1,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (
+                receiver_signature_offset + scalar_size - 1,
+                1,
+                Outcome::VerificationSucceeds,
+            ),
+            (
+                receiver_signature_offset + signature_size - 1,
+                1,
+                Outcome::VerificationSucceeds,
+            ),
+            (
+                dk_flag_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (dk_flag_offset, 2, Outcome::DeserializationFails),
+            (
+                rk_flag_offset,
+                1,
+                Outcome::VerificationFails(KeyFragVerificationError::IncorrectSignature),
+            ),
+            (rk_flag_offset, 2, Outcome::DeserializationFails),
+        ];
+
+        for (offset, mask, expected) in tampered_cases.iter() {
+            let mut tampered = kfrag_arr;
+            tampered[*offset] ^= mask;
+
+            let result = KeyFrag::from_array(&tampered).map(|kfrag| {
+                kfrag.verify(&verifying_pk, Some(&delegating_pk), Some(&receiving_pk))
+            });
+            match expected {
+                Outcome::DeserializationFails => assert!(result.is_err(), "offset {}", offset),
+                Outcome::VerificationFails(error) => {
+                    assert_eq!(
+                        result.unwrap().err().as_ref(),
+                        Some(error),
+                        "offset {}",
+                        offset
+                    )
+                }
+                Outcome::VerificationSucceeds => {
+                    assert!(result.unwrap().is_ok(), "offset {}", offset)
+                }
+            }
+        }
+
+        // A zero `r` is rejected on deserialization, in both signatures
+        for signature_offset in [proxy_signature_offset, receiver_signature_offset].iter() {
+            let mut tampered = kfrag_arr;
+            for byte in tampered[*signature_offset..*signature_offset + scalar_size].iter_mut() {
+                *byte = 0;
+            }
+            assert!(KeyFrag::from_array(&tampered).is_err());
+        }
+    }
+
     #[test]
     fn test_serde_serialization() {
         let (_delegating_pk, _receiving_pk, _verifying_pk, verified_kfrags) =
@@ -573,4 +1011,78 @@ mod tests {
         check_serialization(&kfrag, Representation::Base64);
         check_deserialization(&kfrag);
     }
+
+    #[test]
+    fn test_kfrags_consistency() {
+        let delegating_sk = SecretKey::random();
+        let receiving_pk = SecretKey::random().public_key();
+        let signer = Signer::new(&SecretKey::random());
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let kfrags_other =
+            generate_kfrags(&delegating_sk, &receiving_pk, &signer, 3, 3, true, true);
+
+        assert_eq!(check_kfrags_consistency(&kfrags), Ok(()));
+        assert_eq!(check_kfrags_consistency(&kfrags_other), Ok(()));
+
+        assert_eq!(
+            check_kfrags_consistency(&[]),
+            Err(KeyFragSetError::NoKeyFrags)
+        );
+
+        // Same delegator and receiver, but a different delegation (with a different threshold)
+        let mixed: Vec<_> = kfrags[0..2]
+            .iter()
+            .chain(kfrags_other[0..1].iter())
+            .cloned()
+            .collect();
+        assert_eq!(
+            check_kfrags_consistency(&mixed),
+            Err(KeyFragSetError::MismatchedDelegations)
+        );
+
+        let repeated = [kfrags[0].clone(), kfrags[1].clone(), kfrags[0].clone()];
+        assert_eq!(
+            check_kfrags_consistency(&repeated),
+            Err(KeyFragSetError::RepeatingKeyFrags)
+        );
+    }
+
+    #[test]
+    fn test_delegations_equal() {
+        let delegating_sk = SecretKey::random();
+        let receiving_pk = SecretKey::random().public_key();
+        let signer = Signer::new(&SecretKey::random());
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let kfrags_other =
+            generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+
+        assert!(delegations_equal(&kfrags, &kfrags));
+        assert!(!delegations_equal(&kfrags, &kfrags_other));
+        assert!(delegations_equal(&[], &[]));
+        assert!(!delegations_equal(&kfrags, &[]));
+
+        // Reordered
+        let reordered = [kfrags[2].clone(), kfrags[0].clone(), kfrags[1].clone()];
+        assert!(delegations_equal(&kfrags, &reordered));
+
+        // Repeated fragments do not matter
+        let repeated = [
+            kfrags[1].clone(),
+            kfrags[0].clone(),
+            kfrags[2].clone(),
+            kfrags[0].clone(),
+        ];
+        assert!(delegations_equal(&kfrags, &repeated));
+
+        // Partially overlapping
+        assert!(!delegations_equal(&kfrags[0..2], &kfrags[1..3]));
+        assert!(!delegations_equal(&kfrags, &kfrags[0..2]));
+        assert!(!delegations_equal(&kfrags[0..2], &kfrags));
+
+        // A set mixing fragments of several delegations is not a delegation
+        let mixed = [kfrags[0].clone(), kfrags_other[0].clone()];
+        assert!(!delegations_equal(&mixed, &mixed));
+    }
 }
@@ -0,0 +1,335 @@
+//! Key records for key inventories.
+//!
+//! A [`KeyRecord`] pairs a public key (and, optionally, the corresponding secret key
+//! encrypted with a passphrase) with a label and a creation timestamp,
+//! and can be serialized as a unit.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "default-rng")]
+use rand_core::OsRng;
+
+use crate::dem::EncryptionError;
+use crate::keys::{PublicKey, SecretKey};
+use crate::keystore::{decrypt_with_passphrase, encrypt_with_passphrase, KeystoreError};
+use crate::traits::{
+    ConstructionError, DeserializableFromArray, DeserializationError, RepresentableAsArray,
+    SerializableToArray, SerializableToSecretArray, SizeMismatchError,
+};
+
+const FORMAT_VERSION: u8 = 1;
+const SECRET_KEY_DST: &[u8] = b"KEY_RECORD";
+
+/// A public key (and, optionally, the corresponding secret key encrypted with a passphrase)
+/// along with a label and a creation timestamp.
+///
+/// The metadata is not encrypted, but it is authenticated along with the secret key,
+/// so a record with the metadata changed will fail to decrypt the secret key.
+/// The library does not interpret the timestamp; seconds since the Unix epoch are recommended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyRecord {
+    public_key: PublicKey,
+    label: String,
+    created_at: u64,
+    encrypted_secret_key: Option<Box<[u8]>>,
+}
+
+impl KeyRecord {
+    /// Creates a record of a public key.
+    pub fn new(public_key: &PublicKey, label: &str, created_at: u64) -> Self {
+        Self {
+            public_key: *public_key,
+            label: label.into(),
+            created_at,
+            encrypted_secret_key: None,
+        }
+    }
+
+    /// Creates a record of a secret key and its public key,
+    /// with the secret key encrypted with a key derived from `passphrase`
+    /// (in the same way as [`Keystore`](`crate::Keystore`) is), using the given RNG.
+    pub fn from_secret_key_with_rng(
+        rng: &mut (impl CryptoRng + RngCore),
+        secret_key: &SecretKey,
+        label: &str,
+        created_at: u64,
+        passphrase: &[u8],
+    ) -> Result<Self, EncryptionError> {
+        let mut record = Self::new(&secret_key.public_key(), label, created_at);
+        let encrypted_secret_key = encrypt_with_passphrase(
+            rng,
+            passphrase,
+            &record.secret_key_dst(),
+            secret_key.to_secret_array().as_secret(),
+        )?;
+        record.encrypted_secret_key = Some(encrypted_secret_key);
+        Ok(record)
+    }
+
+    /// A synonym for [`from_secret_key_with_rng`](`Self::from_secret_key_with_rng`)
+    /// with the default RNG.
+    #[cfg(feature = "default-rng")]
+    pub fn from_secret_key(
+        secret_key: &SecretKey,
+        label: &str,
+        created_at: u64,
+        passphrase: &[u8],
+    ) -> Result<Self, EncryptionError> {
+        Self::from_secret_key_with_rng(&mut OsRng, secret_key, label, created_at, passphrase)
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Returns the label.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the creation timestamp.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Returns `true` if the record contains an encrypted secret key.
+    pub fn has_secret_key(&self) -> bool {
+        self.encrypted_secret_key.is_some()
+    }
+
+    /// Decrypts the secret key with a key derived from `passphrase`.
+    /// Returns `None` if the record does not contain a secret key.
+    ///
+    /// Returns [`KeystoreError::AuthenticationFailed`] if the passphrase is incorrect,
+    /// or the record (including the metadata) was modified.
+    pub fn secret_key(&self, passphrase: &[u8]) -> Result<Option<SecretKey>, KeystoreError> {
+        let encrypted_secret_key = match &self.encrypted_secret_key {
+            Some(encrypted_secret_key) => encrypted_secret_key,
+            None => return Ok(None),
+        };
+
+        let key_bytes =
+            decrypt_with_passphrase(passphrase, &self.secret_key_dst(), encrypted_secret_key)?;
+        let secret_key =
+            SecretKey::from_bytes(key_bytes.as_secret()).or(Err(KeystoreError::InvalidContents))?;
+        if secret_key.public_key() != self.public_key {
+            return Err(KeystoreError::InvalidContents);
+        }
+        Ok(Some(secret_key))
+    }
+
+    /// Serializes the format version and the metadata (everything but the secret key).
+    fn metadata_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::<u8>::new();
+        result.push(FORMAT_VERSION);
+        result.extend_from_slice(&self.public_key.to_array());
+        result.extend_from_slice(&self.created_at.to_be_bytes());
+        result.extend_from_slice(&(self.label.len() as u32).to_be_bytes());
+        result.extend_from_slice(self.label.as_bytes());
+        result
+    }
+
+    fn secret_key_dst(&self) -> Vec<u8> {
+        let mut dst = Vec::<u8>::from(SECRET_KEY_DST);
+        dst.extend_from_slice(&self.metadata_bytes());
+        dst
+    }
+
+    /// Serializes the record.
+    ///
+    /// The format is: the format version (1 byte), the public key,
+    /// the timestamp (8 bytes, big-endian), the label length (4 bytes, big-endian),
+    /// the label (UTF-8), `1` followed by the encrypted secret key if the record contains it,
+    /// or `0` otherwise.
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        let mut result = self.metadata_bytes();
+        match &self.encrypted_secret_key {
+            Some(encrypted_secret_key) => {
+                result.push(1);
+                result.extend_from_slice(encrypted_secret_key);
+            }
+            None => result.push(0),
+        }
+        result.into_boxed_slice()
+    }
+
+    /// Restores a record serialized by [`to_bytes`](`Self::to_bytes`).
+    ///
+    /// The encrypted secret key is not checked until [`secret_key`](`Self::secret_key`) is called.
+    pub fn from_bytes(data: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
+        let data = data.as_ref();
+        let size_mismatch = |expected_size| {
+            DeserializationError::SizeMismatch(SizeMismatchError::new(data.len(), expected_size))
+        };
+
+        let version = *data.first().ok_or_else(|| size_mismatch(1))?;
+        if version != FORMAT_VERSION {
+            return Err(DeserializationError::UnsupportedVersion(version));
+        }
+
+        let key_size = PublicKey::serialized_size();
+        let header_size = 1 + key_size + 8 + 4;
+        if data.len() < header_size + 1 {
+            return Err(size_mismatch(header_size + 1));
+        }
+
+        let public_key = PublicKey::from_bytes(&data[1..1 + key_size])?;
+        let created_at =
+            u64::from_be_bytes(data[1 + key_size..1 + key_size + 8].try_into().unwrap());
+        let label_len =
+            u32::from_be_bytes(data[header_size - 4..header_size].try_into().unwrap()) as usize;
+        // The label length is untrusted, so the end offset can overflow on 32-bit targets.
+        // The label must be followed by the secret key flag.
+        let label_end = match header_size.checked_add(label_len) {
+            Some(label_end) if label_end < data.len() => label_end,
+            _ => {
+                return Err(size_mismatch(
+                    header_size.saturating_add(label_len).saturating_add(1),
+                ))
+            }
+        };
+
+        let label = String::from_utf8(data[header_size..label_end].into()).or(Err(
+            DeserializationError::ConstructionFailure(ConstructionError::new(
+                "KeyRecord",
+                "The label is not a valid UTF-8 string",
+            )),
+        ))?;
+
+        let flag = data[label_end];
+        let rest = &data[label_end + 1..];
+        let encrypted_secret_key = match flag {
+            0 if rest.is_empty() => None,
+            0 => return Err(size_mismatch(data.len() - rest.len())),
+            1 if !rest.is_empty() => Some(rest.into()),
+            1 => return Err(size_mismatch(data.len() + 1)),
+            _ => {
+                return Err(DeserializationError::ConstructionFailure(
+                    ConstructionError::new("KeyRecord", "Invalid secret key flag"),
+                ))
+            }
+        };
+
+        Ok(Self {
+            public_key,
+            label,
+            created_at,
+            encrypted_secret_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::KeyRecord;
+    use crate::{DeserializationError, KeystoreError, SecretKey};
+
+    // The format version, the salt, the nonce, the secret key, and the authentication tag.
+    const ENCRYPTED_SECRET_KEY_SIZE: usize = 1 + 16 + 24 + 32 + 16;
+
+    #[test]
+    fn test_public_key_record() {
+        let pk = SecretKey::random().public_key();
+        let record = KeyRecord::new(&pk, "alice", 1_600_000_000);
+        assert_eq!(record.public_key(), pk);
+        assert_eq!(record.label(), "alice");
+        assert_eq!(record.created_at(), 1_600_000_000);
+        assert!(!record.has_secret_key());
+        assert!(record.secret_key(b"correct horse").unwrap().is_none());
+
+        let data = record.to_bytes();
+        assert_eq!(KeyRecord::from_bytes(&data).unwrap(), record);
+
+        // An empty label
+        let record = KeyRecord::new(&pk, "", 0);
+        assert_eq!(KeyRecord::from_bytes(record.to_bytes()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_secret_key_record() {
+        let sk = SecretKey::random();
+        let record =
+            KeyRecord::from_secret_key(&sk, "alice", 1_600_000_000, b"correct horse").unwrap();
+        assert_eq!(record.public_key(), sk.public_key());
+        assert!(record.has_secret_key());
+
+        let data = record.to_bytes();
+        assert_eq!(
+            data.len(),
+            1 + 33 + 8 + 4 + 5 + 1 + ENCRYPTED_SECRET_KEY_SIZE
+        );
+
+        let record_back = KeyRecord::from_bytes(&data).unwrap();
+        assert_eq!(record_back, record);
+        assert!(record_back.secret_key(b"correct horse").unwrap().unwrap() == sk);
+
+        // Wrong passphrase
+        assert_eq!(
+            record_back
+                .secret_key(b"battery staple")
+                .map(|sk| sk.is_some()),
+            Err(KeystoreError::AuthenticationFailed)
+        );
+
+        // Changed metadata (a different label of the same length)
+        let mut tampered = record.to_bytes().to_vec();
+        let label_start = tampered.len() - ENCRYPTED_SECRET_KEY_SIZE - 1 - "alice".len();
+        tampered[label_start] = b'A';
+        let record_tampered = KeyRecord::from_bytes(&tampered).unwrap();
+        assert_eq!(record_tampered.label(), "Alice");
+        assert_eq!(
+            record_tampered
+                .secret_key(b"correct horse")
+                .map(|sk| sk.is_some()),
+            Err(KeystoreError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let sk = SecretKey::random();
+        let record = KeyRecord::from_secret_key(&sk, "alice", 0, b"correct horse").unwrap();
+        let data = record.to_bytes();
+
+        // Truncated before the encrypted secret key
+        for size in 0..=(data.len() - ENCRYPTED_SECRET_KEY_SIZE) {
+            assert!(matches!(
+                KeyRecord::from_bytes(&data[..size]),
+                Err(DeserializationError::SizeMismatch(_))
+            ));
+        }
+
+        // Unknown format version
+        let mut tampered = data.to_vec();
+        tampered[0] = 0;
+        assert_eq!(
+            KeyRecord::from_bytes(&tampered),
+            Err(DeserializationError::UnsupportedVersion(0))
+        );
+
+        // A label length exceeding the data
+        let mut tampered = data.to_vec();
+        tampered[1 + 33 + 8..1 + 33 + 8 + 4].copy_from_slice(&0xffff_ffffu32.to_be_bytes());
+        assert!(matches!(
+            KeyRecord::from_bytes(&tampered),
+            Err(DeserializationError::SizeMismatch(_))
+        ));
+
+        // Trailing data without a secret key
+        let mut trailing = KeyRecord::new(&sk.public_key(), "alice", 0)
+            .to_bytes()
+            .to_vec();
+        trailing.push(0);
+        assert!(matches!(
+            KeyRecord::from_bytes(&trailing),
+            Err(DeserializationError::SizeMismatch(_))
+        ));
+    }
+}
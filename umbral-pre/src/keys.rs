@@ -10,6 +10,7 @@ use generic_array::GenericArray;
 use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use signature::{DigestVerifier, RandomizedDigestSigner, Signature as SignatureTrait};
+use subtle::ConstantTimeEq;
 use typenum::{Unsigned, U32, U64};
 
 #[cfg(feature = "default-rng")]
@@ -26,6 +27,13 @@ use crate::traits::{
 };
 
 /// ECDSA signature object.
+///
+/// Serialized in the canonical form: 64 bytes consisting of the big-endian encodings
+/// of `r` and `s`, 32 bytes each, both in the range `[1, n - 1]` (where `n` is the curve order),
+/// and with `s` in the lower half of it (`s <= n / 2`, the "low-s" form of BIP 62).
+/// Signatures produced by [`Signer`] are always in this form,
+/// and deserialization rejects any other one (including the equally valid "high-s" counterpart),
+/// so a signature has a unique byte representation.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Signature(BackendSignature<CurveType>);
 
@@ -41,11 +49,24 @@ impl SerializableToArray for Signature {
 
 impl DeserializableFromArray for Signature {
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Result<Self, ConstructionError> {
-        // Note that it will not normalize `s` automatically,
-        // and if it is not normalized, verification will fail.
-        BackendSignature::<CurveType>::from_bytes(arr.as_slice())
-            .map(Self)
-            .map_err(|_| ConstructionError::new("Signature", "Internal backend error"))
+        // Checks that `r` and `s` are non-zero and reduced modulo the curve order.
+        let signature = BackendSignature::<CurveType>::from_bytes(arr.as_slice())
+            .map_err(|_| ConstructionError::new("Signature", "Internal backend error"))?;
+
+        // A "high-s" signature would fail verification anyway,
+        // but we also want the serialized form to be unique.
+        let mut normalized = signature;
+        let was_high = normalized
+            .normalize_s()
+            .map_err(|_| ConstructionError::new("Signature", "Internal backend error"))?;
+        if was_high {
+            return Err(ConstructionError::new(
+                "Signature",
+                "Non-canonical signature (s is not normalized)",
+            ));
+        }
+
+        Ok(Self(signature))
     }
 }
 
@@ -171,6 +192,40 @@ fn digest_for_signing(message: &[u8]) -> BackendDigest {
     Hash::new().chain_bytes(message).digest()
 }
 
+/// Verifies several signatures of the same message,
+/// each with its own verifying key.
+/// The message is hashed only once, which is faster than calling [`Signature::verify`]
+/// for every signature if the message is large.
+///
+/// Returns the verification result for every signature, in the same order.
+pub fn verify_same_message(signatures: &[(Signature, PublicKey)], message: &[u8]) -> Box<[bool]> {
+    verify_same_message_with_progress(signatures, message, signatures.len(), |_, _| {})
+}
+
+/// Same as [`verify_same_message`], but calls `progress` with the number of verified signatures
+/// and the total number of signatures after every `progress_every` signatures,
+/// and after the last one (only after the last one if `progress_every` is zero).
+pub fn verify_same_message_with_progress(
+    signatures: &[(Signature, PublicKey)],
+    message: &[u8],
+    progress_every: usize,
+    mut progress: impl FnMut(usize, usize),
+) -> Box<[bool]> {
+    let digest = digest_for_signing(message);
+    let total = signatures.len();
+    let mut results = Vec::<bool>::with_capacity(total);
+    let mut since_report = 0;
+    for (signature, verifying_pk) in signatures {
+        results.push(verifying_pk.verify_digest(digest.clone(), signature));
+        since_report += 1;
+        if since_report == progress_every || results.len() == total {
+            progress(results.len(), total);
+            since_report = 0;
+        }
+    }
+    results.into_boxed_slice()
+}
+
 /// An object used to sign messages.
 /// For security reasons cannot be serialized.
 #[derive(Clone)]
@@ -202,6 +257,19 @@ impl Signer {
     pub fn verifying_key(&self) -> PublicKey {
         self.0.public_key()
     }
+
+    /// Returns `true` if `verifying_pk` is the public key that verifies
+    /// the signatures produced by this signer.
+    ///
+    /// Intended to check the key that will be distributed to the verifying parties
+    /// (e.g. before calling [`generate_kfrags`](`crate::generate_kfrags`)).
+    /// The comparison is performed in constant time.
+    pub fn verifying_key_equals(&self, verifying_pk: &PublicKey) -> bool {
+        self.verifying_key()
+            .to_array()
+            .ct_eq(&verifying_pk.to_array())
+            .into()
+    }
 }
 
 impl HasTypeName for Signer {
@@ -310,14 +378,26 @@ type SecretKeyFactorySeed = GenericArray<u8, SecretKeyFactorySeedSize>;
 
 /// This class handles keyring material for Umbral, by allowing deterministic
 /// derivation of `SecretKey` objects based on labels.
+///
+/// The derivation is guaranteed to produce the same keys for the same seed and label
+/// as long as [`DERIVATION_VERSION`](`Self::DERIVATION_VERSION`) stays the same.
 #[derive(Clone)]
 pub struct SecretKeyFactory(SecretBox<SecretKeyFactorySeed>);
 
 impl SecretKeyFactory {
+    /// The version of the derivation algorithm used by
+    /// [`make_key`](`Self::make_key`) and [`make_factory`](`Self::make_factory`).
+    ///
+    /// It is increased on any change that makes these methods
+    /// produce different results for the same seed and label.
+    /// Services storing factory seeds long-term can save this value along with them
+    /// and compare it on startup to detect an incompatible library version.
+    pub const DERIVATION_VERSION: u32 = 1;
+
     /// Creates a secret key factory using the given RNG.
     pub fn random_with_rng(rng: &mut (impl CryptoRng + RngCore)) -> Self {
         let mut bytes = SecretBox::new(GenericArray::<u8, SecretKeyFactorySeedSize>::default());
-        rng.fill_bytes(&mut bytes.as_mut_secret());
+        rng.fill_bytes(bytes.as_mut_secret());
         Self(bytes)
     }
 
@@ -367,6 +447,17 @@ impl SecretKeyFactory {
         SecretKey::from_scalar(&scalar).ok_or(SecretKeyFactoryError::ZeroHash)
     }
 
+    /// Creates a `SecretKey` deterministically from the given label
+    /// (same as [`make_key`](`Self::make_key`)), and returns it along with its public key.
+    pub fn receiving_keypair_by_label(
+        &self,
+        label: &[u8],
+    ) -> Result<(SecretKey, PublicKey), SecretKeyFactoryError> {
+        let sk = self.make_key(label)?;
+        let pk = sk.public_key();
+        Ok((sk, pk))
+    }
+
     /// Creates a `SecretKeyFactory` deterministically from the given label.
     pub fn make_factory(&self, label: &[u8]) -> Self {
         let prefix = b"FACTORY_DERIVATION/";
@@ -419,7 +510,12 @@ impl fmt::Display for SecretKeyFactory {
 #[cfg(test)]
 mod tests {
 
-    use super::{PublicKey, SecretKey, SecretKeyFactory, Signer};
+    use alloc::vec::Vec;
+
+    use super::{
+        verify_same_message, verify_same_message_with_progress, PublicKey, SecretKey,
+        SecretKeyFactory, Signature, Signer,
+    };
     use crate::serde::tests::{check_deserialization, check_serialization};
     use crate::serde::Representation;
     use crate::{DeserializableFromArray, SerializableToArray, SerializableToSecretArray};
@@ -451,6 +547,41 @@ mod tests {
         assert!(sk1 != sk3);
     }
 
+    #[test]
+    fn test_receiving_keypair_by_label() {
+        let skf = SecretKeyFactory::random();
+        let (sk, pk) = skf.receiving_keypair_by_label(b"foo").unwrap();
+        assert_eq!(sk.public_key(), pk);
+
+        // Same as the key derived separately
+        let sk_separate = skf.make_key(b"foo").unwrap();
+        assert_eq!(sk_separate.public_key(), pk);
+
+        let (_sk_other, pk_other) = skf.receiving_keypair_by_label(b"bar").unwrap();
+        assert_ne!(pk_other, pk);
+    }
+
+    #[test]
+    fn test_secret_key_factory_derivation_stability() {
+        // Known-answer test: if this fails, the key derivation has changed,
+        // and `SecretKeyFactory::DERIVATION_VERSION` must be increased.
+        assert_eq!(SecretKeyFactory::DERIVATION_VERSION, 1);
+
+        let skf = SecretKeyFactory::from_secure_randomness(&[7u8; 32]).unwrap();
+
+        let sk = skf.make_key(b"alice").unwrap();
+        assert_eq!(
+            hex::encode(sk.to_secret_array().as_secret()),
+            "594c7f873b81c55338f0d89c9a32c932990118343cdbc669e9cf205ada7dafbf"
+        );
+
+        let skf_derived = skf.make_factory(b"alice");
+        assert_eq!(
+            hex::encode(skf_derived.to_secret_array().as_secret()),
+            "ff27a93b77d23f8d511e31f116a6e6d9124ebe6faaf82d7f0a1a69c8db1d4e71"
+        );
+    }
+
     #[test]
     fn test_serialize_public_key() {
         let sk = SecretKey::random();
@@ -460,6 +591,54 @@ mod tests {
         assert_eq!(pk, pk_back);
     }
 
+    #[test]
+    fn test_signature_canonical_form() {
+        // Test vectors from `k256`: the same signature with a "high" and a "low" `s`.
+        #[rustfmt::skip]
+        let sig_high: [u8; 64] = [
+            0x20, 0xc0, 0x1a, 0x91, 0x0e, 0xbb, 0x26, 0x10,
+            0xaf, 0x2d, 0x76, 0x3f, 0xa0, 0x9b, 0x3b, 0x30,
+            0x92, 0x3c, 0x8e, 0x40, 0x8b, 0x11, 0xdf, 0x2c,
+            0x61, 0xad, 0x76, 0xd9, 0x70, 0xa2, 0xf1, 0xbc,
+            0xee, 0x2f, 0x11, 0xef, 0x8c, 0xb0, 0x0a, 0x49,
+            0x61, 0x7d, 0x13, 0x57, 0xf4, 0xd5, 0x56, 0x41,
+            0x09, 0x0a, 0x48, 0xf2, 0x01, 0xe9, 0xb9, 0x59,
+            0xc4, 0x8f, 0x6f, 0x6b, 0xec, 0x6f, 0x93, 0x8f,
+        ];
+        #[rustfmt::skip]
+        let sig_low: [u8; 64] = [
+            0x20, 0xc0, 0x1a, 0x91, 0x0e, 0xbb, 0x26, 0x10,
+            0xaf, 0x2d, 0x76, 0x3f, 0xa0, 0x9b, 0x3b, 0x30,
+            0x92, 0x3c, 0x8e, 0x40, 0x8b, 0x11, 0xdf, 0x2c,
+            0x61, 0xad, 0x76, 0xd9, 0x70, 0xa2, 0xf1, 0xbc,
+            0x11, 0xd0, 0xee, 0x10, 0x73, 0x4f, 0xf5, 0xb6,
+            0x9e, 0x82, 0xec, 0xa8, 0x0b, 0x2a, 0xa9, 0xbd,
+            0xb1, 0xa4, 0x93, 0xf4, 0xad, 0x5e, 0xe6, 0xe1,
+            0xfb, 0x42, 0xef, 0x20, 0xe3, 0xc6, 0xad, 0xb2,
+        ];
+
+        assert!(Signature::from_bytes(sig_high).is_err());
+        let signature = Signature::from_bytes(sig_low).unwrap();
+        assert_eq!(signature.to_array().as_slice(), &sig_low[..]);
+
+        // Zero and unreduced scalars are rejected too
+        let mut sig_zero_s = sig_low;
+        sig_zero_s[32..].copy_from_slice(&[0u8; 32]);
+        assert!(Signature::from_bytes(sig_zero_s).is_err());
+
+        let mut sig_unreduced_r = sig_low;
+        sig_unreduced_r[..32].copy_from_slice(&[0xffu8; 32]);
+        assert!(Signature::from_bytes(sig_unreduced_r).is_err());
+
+        // Produced signatures are always canonical
+        let signer = Signer::new(&SecretKey::random());
+        for i in 0..16u8 {
+            let signature = signer.sign(&[i]);
+            let signature_back = Signature::from_bytes(signature.to_array()).unwrap();
+            assert_eq!(signature_back, signature);
+        }
+    }
+
     #[test]
     fn test_sign_and_verify() {
         let sk = SecretKey::random();
@@ -472,6 +651,44 @@ mod tests {
 
         assert_eq!(pk, vk);
         assert!(signature.verify(&vk, message));
+
+        assert!(signer.verifying_key_equals(&pk));
+        assert!(!signer.verifying_key_equals(&SecretKey::random().public_key()));
+    }
+
+    #[test]
+    fn test_verify_same_message() {
+        let message = b"asdafdahsfdasdfasd";
+        let signing_sks = [
+            SecretKey::random(),
+            SecretKey::random(),
+            SecretKey::random(),
+        ];
+        let mut signatures: Vec<_> = signing_sks
+            .iter()
+            .map(|sk| (Signer::new(sk).sign(message), sk.public_key()))
+            .collect();
+
+        // A signature of a different message
+        let other_sk = SecretKey::random();
+        signatures.push((
+            Signer::new(&other_sk).sign(b"something else"),
+            other_sk.public_key(),
+        ));
+
+        // A signature with a different verifying key
+        signatures[1].1 = other_sk.public_key();
+
+        let results = verify_same_message(&signatures, message);
+        assert_eq!(results.as_ref(), [true, false, true, false]);
+
+        let mut reports = Vec::new();
+        let results_with_progress =
+            verify_same_message_with_progress(&signatures, message, 3, |done, total| {
+                reports.push((done, total))
+            });
+        assert_eq!(results_with_progress, results);
+        assert_eq!(reports, [(3, 4), (4, 4)]);
     }
 
     #[test]
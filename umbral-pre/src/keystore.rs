@@ -0,0 +1,301 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+
+use generic_array::GenericArray;
+use hmac::Hmac;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use typenum::{Unsigned, U16, U32};
+
+#[cfg(feature = "default-rng")]
+use rand_core::OsRng;
+
+use crate::dem::{EncryptionError, DEM};
+use crate::keys::SecretKey;
+use crate::secret_box::SecretBox;
+use crate::traits::{DeserializableFromArray, RepresentableAsArray, SerializableToSecretArray};
+
+/// Errors that can happen when decrypting a [`Keystore`].
+#[derive(Debug, PartialEq)]
+pub enum KeystoreError {
+    /// The data is too short, or was created by an unsupported version of the library.
+    UnknownFormat,
+    /// The data could not be authenticated.
+    /// This can happen if an incorrect passphrase is used, or the data was modified.
+    AuthenticationFailed,
+    /// The data was authenticated, but its contents cannot be parsed.
+    InvalidContents,
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFormat => write!(f, "Unknown keystore format"),
+            Self::AuthenticationFailed => write!(
+                f,
+                "Keystore authentication failed: \
+                either the passphrase is incorrect or the data was tampered with"
+            ),
+            Self::InvalidContents => write!(f, "Invalid keystore contents"),
+        }
+    }
+}
+
+const FORMAT_VERSION: u8 = 1;
+const PBKDF2_ROUNDS: u32 = 100_000;
+type SaltSize = U16;
+type PassphraseKeySize = U32;
+
+fn derive_passphrase_key(
+    passphrase: &[u8],
+    salt: &[u8],
+) -> SecretBox<GenericArray<u8, PassphraseKeySize>> {
+    let mut key = SecretBox::new(GenericArray::<u8, PassphraseKeySize>::default());
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase, salt, PBKDF2_ROUNDS, key.as_mut_secret());
+    key
+}
+
+/// Encrypts `plaintext` with a key derived from `passphrase`
+/// (using PBKDF2-HMAC-SHA256 with a random salt).
+/// `dst` is a domain separation tag authenticated along with the ciphertext.
+///
+/// The result consists of the format version, the salt, and the DEM ciphertext.
+pub(crate) fn encrypt_with_passphrase(
+    rng: &mut (impl CryptoRng + RngCore),
+    passphrase: &[u8],
+    dst: &[u8],
+    plaintext: &[u8],
+) -> Result<Box<[u8]>, EncryptionError> {
+    let mut salt = GenericArray::<u8, SaltSize>::default();
+    rng.fill_bytes(&mut salt);
+
+    let mut header = Vec::<u8>::new();
+    header.push(FORMAT_VERSION);
+    header.extend_from_slice(&salt);
+
+    let mut aad = Vec::<u8>::from(dst);
+    aad.extend_from_slice(&header);
+
+    let key = derive_passphrase_key(passphrase, &salt);
+    let ciphertext = DEM::new(&key).encrypt(rng, plaintext, &aad)?;
+
+    let mut result = header;
+    result.extend_from_slice(&ciphertext);
+    Ok(result.into_boxed_slice())
+}
+
+/// Decrypts data created by [`encrypt_with_passphrase`].
+pub(crate) fn decrypt_with_passphrase(
+    passphrase: &[u8],
+    dst: &[u8],
+    data: &[u8],
+) -> Result<SecretBox<Vec<u8>>, KeystoreError> {
+    let header_size = 1 + SaltSize::to_usize();
+    if data.len() < header_size || data[0] != FORMAT_VERSION {
+        return Err(KeystoreError::UnknownFormat);
+    }
+    let (header, ciphertext) = data.split_at(header_size);
+    let salt = &header[1..];
+
+    let mut aad = Vec::<u8>::from(dst);
+    aad.extend_from_slice(header);
+
+    let key = derive_passphrase_key(passphrase, salt);
+    DEM::new(&key)
+        .decrypt(ciphertext, &aad)
+        .map(|plaintext| SecretBox::new(plaintext.into_vec()))
+        .or(Err(KeystoreError::AuthenticationFailed))
+}
+
+/// A collection of named secret keys that can be stored
+/// in a single passphrase-protected bytestring.
+#[derive(Clone, Default)]
+pub struct Keystore {
+    keys: BTreeMap<String, SecretKey>,
+}
+
+impl Keystore {
+    /// Creates an empty keystore.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key under the given name, returning the key previously stored under it (if any).
+    pub fn add(&mut self, name: &str, secret_key: &SecretKey) -> Option<SecretKey> {
+        self.keys.insert(name.into(), secret_key.clone())
+    }
+
+    /// Returns the key stored under the given name.
+    pub fn get(&self, name: &str) -> Option<&SecretKey> {
+        self.keys.get(name)
+    }
+
+    /// Removes the key stored under the given name, returning it (if any).
+    pub fn remove(&mut self, name: &str) -> Option<SecretKey> {
+        self.keys.remove(name)
+    }
+
+    /// Returns the names of the stored keys in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(|name| name.as_str())
+    }
+
+    /// Returns the number of stored keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if the keystore contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Serializes the keystore and encrypts it with a key derived from `passphrase`,
+    /// using the given RNG.
+    pub fn encrypt_with_rng(
+        &self,
+        rng: &mut (impl CryptoRng + RngCore),
+        passphrase: &[u8],
+    ) -> Result<Box<[u8]>, EncryptionError> {
+        // Preallocating to avoid reallocations leaving copies of the secret data behind.
+        let contents_size = self
+            .keys
+            .keys()
+            .map(|name| 4 + name.len() + SecretKey::serialized_size())
+            .sum();
+        let mut contents = SecretBox::new(Vec::<u8>::with_capacity(contents_size));
+        for (name, secret_key) in self.keys.iter() {
+            let contents = contents.as_mut_secret();
+            contents.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            contents.extend_from_slice(name.as_bytes());
+            contents.extend_from_slice(secret_key.to_secret_array().as_secret());
+        }
+        encrypt_with_passphrase(rng, passphrase, b"KEYSTORE", contents.as_secret())
+    }
+
+    /// A synonym for [`encrypt_with_rng`](`Self::encrypt_with_rng`) with the default RNG.
+    #[cfg(feature = "default-rng")]
+    pub fn encrypt(&self, passphrase: &[u8]) -> Result<Box<[u8]>, EncryptionError> {
+        self.encrypt_with_rng(&mut OsRng, passphrase)
+    }
+
+    /// Decrypts a keystore created by [`encrypt`](`Self::encrypt`).
+    pub fn decrypt(data: impl AsRef<[u8]>, passphrase: &[u8]) -> Result<Self, KeystoreError> {
+        let contents = decrypt_with_passphrase(passphrase, b"KEYSTORE", data.as_ref())?;
+        let key_size = SecretKey::serialized_size();
+
+        let mut keys = BTreeMap::<String, SecretKey>::new();
+        let mut remainder: &[u8] = contents.as_secret();
+        while !remainder.is_empty() {
+            if remainder.len() < 4 {
+                return Err(KeystoreError::InvalidContents);
+            }
+            let (name_len, rest) = remainder.split_at(4);
+            let name_len = u32::from_be_bytes(name_len.try_into().unwrap()) as usize;
+            match name_len.checked_add(key_size) {
+                Some(entry_size) if entry_size <= rest.len() => {}
+                _ => return Err(KeystoreError::InvalidContents),
+            }
+            let (name, rest) = rest.split_at(name_len);
+            let (key_bytes, rest) = rest.split_at(key_size);
+            let name = String::from_utf8(name.into()).or(Err(KeystoreError::InvalidContents))?;
+            let secret_key =
+                SecretKey::from_bytes(key_bytes).or(Err(KeystoreError::InvalidContents))?;
+            keys.insert(name, secret_key);
+            remainder = rest;
+        }
+
+        Ok(Self { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use rand_core::OsRng;
+
+    use super::{encrypt_with_passphrase, Keystore, KeystoreError};
+    use crate::SecretKey;
+
+    #[test]
+    fn test_add_get_remove() {
+        let sk1 = SecretKey::random();
+        let sk2 = SecretKey::random();
+
+        let mut keystore = Keystore::new();
+        assert!(keystore.is_empty());
+
+        assert!(keystore.add("alice", &sk1).is_none());
+        assert!(keystore.add("bob", &sk1).is_none());
+        assert!(keystore.add("bob", &sk2).unwrap() == sk1);
+        assert_eq!(keystore.len(), 2);
+
+        assert!(keystore.get("alice").unwrap() == &sk1);
+        assert!(keystore.get("bob").unwrap() == &sk2);
+        assert!(keystore.get("carol").is_none());
+
+        assert!(keystore.remove("alice").unwrap() == sk1);
+        assert!(keystore.remove("alice").is_none());
+        assert_eq!(keystore.names().collect::<Vec<_>>(), ["bob"]);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let sk1 = SecretKey::random();
+        let sk2 = SecretKey::random();
+
+        let mut keystore = Keystore::new();
+        keystore.add("alice", &sk1);
+        keystore.add("bob", &sk2);
+
+        let data = keystore.encrypt(b"correct horse").unwrap();
+
+        let keystore_back = Keystore::decrypt(&data, b"correct horse").unwrap();
+        assert_eq!(keystore_back.len(), 2);
+        assert!(keystore_back.get("alice").unwrap() == &sk1);
+        assert!(keystore_back.get("bob").unwrap() == &sk2);
+
+        // Wrong passphrase
+        assert_eq!(
+            Keystore::decrypt(&data, b"battery staple").map(|ks| ks.len()),
+            Err(KeystoreError::AuthenticationFailed)
+        );
+
+        // Tampered data (a bit flipped in the salt)
+        let mut tampered = data.to_vec();
+        tampered[1] ^= 1;
+        assert_eq!(
+            Keystore::decrypt(&tampered, b"correct horse").map(|ks| ks.len()),
+            Err(KeystoreError::AuthenticationFailed)
+        );
+
+        // Unknown format version
+        let mut tampered = data.to_vec();
+        tampered[0] = 0;
+        assert_eq!(
+            Keystore::decrypt(&tampered, b"correct horse").map(|ks| ks.len()),
+            Err(KeystoreError::UnknownFormat)
+        );
+    }
+
+    #[test]
+    fn test_decrypt_invalid_name_length() {
+        // A correctly encrypted keystore whose only entry claims a name of `u32::MAX` bytes.
+        // Checks that the entry size computation does not overflow on 32-bit targets.
+        let mut contents = Vec::<u8>::new();
+        contents.extend_from_slice(&u32::MAX.to_be_bytes());
+        contents.extend_from_slice(b"alice");
+        let data =
+            encrypt_with_passphrase(&mut OsRng, b"correct horse", b"KEYSTORE", &contents).unwrap();
+        assert_eq!(
+            Keystore::decrypt(&data, b"correct horse").map(|ks| ks.len()),
+            Err(KeystoreError::InvalidContents)
+        );
+    }
+}
@@ -24,6 +24,10 @@
 //! let signer = Signer::new(&signing_sk);
 //! let verifying_pk = signing_sk.public_key();
 //!
+//! // Make sure the key to be distributed to Ursulas and Bob for verification
+//! // is the one corresponding to the signer.
+//! assert!(signer.verifying_key_equals(&verifying_pk));
+//!
 //! // Key Generation (on Bob's side)
 //! let bob_sk = SecretKey::random();
 //! let bob_pk = bob_sk.public_key();
@@ -102,37 +106,80 @@
 
 extern crate alloc;
 
-#[cfg(feature = "bench-internals")]
-pub mod bench; // Re-export some internals for benchmarks.
+#[cfg(any(test, feature = "bench-internals"))]
+pub mod bench; // Re-export some internals for benchmarks, and dummy objects for tests.
 
 mod capsule;
 mod capsule_frag;
+mod combination;
 mod curve;
 mod dem;
 mod hashing;
 mod hashing_ds;
+mod kem;
 mod key_frag;
+mod key_record;
 mod keys;
+mod keystore;
+mod metrics;
 mod params;
 mod pre;
+mod record;
 mod secret_box;
 mod serde;
 mod traits;
 
 pub use capsule::{Capsule, OpenReencryptedError};
-pub use capsule_frag::{CapsuleFrag, CapsuleFragVerificationError, VerifiedCapsuleFrag};
-pub use dem::{DecryptionError, EncryptionError};
-pub use key_frag::{KeyFrag, KeyFragVerificationError, VerifiedKeyFrag};
-pub use keys::{PublicKey, SecretKey, SecretKeyFactory, SecretKeyFactoryError, Signature, Signer};
+pub use capsule_frag::{
+    deserialize_cfrags_compact, parse_cfrags, serialize_cfrags_compact, CapsuleFrag,
+    CapsuleFragVerificationError, CapsuleFragVerifier, CompactCapsuleFragsError,
+    VerifiedCapsuleFrag,
+};
+pub use combination::{
+    combination_coefficients, combine_cfrags, CombinationCoefficient, CombinedCapsuleFrags,
+};
+pub use dem::{hkdf, DecryptionError, EncryptionError, KdfError};
+pub use kem::{
+    derive_symmetric_key_with_rng, open_reencrypted_symmetric_key, open_symmetric_key,
+    SymmetricKeySize,
+};
+pub use key_frag::{
+    check_kfrags_consistency, delegations_equal, KeyFrag, KeyFragCommitment, KeyFragSetError,
+    KeyFragVerificationError, VerifiedKeyFrag,
+};
+pub use key_record::KeyRecord;
+pub use keys::{
+    verify_same_message, verify_same_message_with_progress, PublicKey, SecretKey, SecretKeyFactory,
+    SecretKeyFactoryError, Signature, Signer,
+};
+pub use keystore::{Keystore, KeystoreError};
+pub use params::{backend_name, features, security_parameters, SecurityParameters};
 pub use pre::{
-    decrypt_original, decrypt_reencrypted, encrypt_with_rng, generate_kfrags_with_rng,
-    reencrypt_with_rng, ReencryptionError,
+    decrypt_combined, decrypt_original, decrypt_original_detached, decrypt_original_into,
+    decrypt_reencrypted, decrypt_reencrypted_detached, decrypt_reencrypted_into,
+    encrypt_detached_with_rng, encrypt_with_rng, generate_deterministic_kfrags_with_rng,
+    generate_kfrags_with_rng, generate_labeled_kfrags_with_rng, is_owner, plaintext_size,
+    reencrypt_with_rng, same_capsule, self_encrypt_decrypt_with_rng, validate_grant_with_rng,
+    LabeledKeyFragsError, ReencryptionError,
 };
+pub use record::{decrypt_original_record, decrypt_reencrypted_record, encrypt_record_with_rng};
 pub use secret_box::{CanBeZeroizedOnDrop, SecretBox};
 pub use traits::{
     ConstructionError, DeserializableFromArray, DeserializationError, HasTypeName,
     RepresentableAsArray, SerializableToArray, SerializableToSecretArray, SizeMismatchError,
 };
 
+#[cfg(feature = "metrics")]
+pub use metrics::{metrics, reset_metrics, Metrics, OperationCounts};
+
+#[cfg(feature = "default-rng")]
+pub use kem::derive_symmetric_key;
+
+#[cfg(feature = "default-rng")]
+pub use pre::{
+    encrypt, encrypt_detached, generate_deterministic_kfrags, generate_kfrags,
+    generate_labeled_kfrags, reencrypt, self_encrypt_decrypt, validate_grant,
+};
+
 #[cfg(feature = "default-rng")]
-pub use pre::{encrypt, generate_kfrags, reencrypt};
+pub use record::encrypt_record;
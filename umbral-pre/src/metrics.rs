@@ -0,0 +1,202 @@
+//! In-process counters of the operations performed by the library.
+//!
+//! The counting is only performed if the `metrics` feature is enabled;
+//! otherwise recording an operation is a no-op, and [`metrics`] and [`reset_metrics`]
+//! are not available.
+
+#[cfg(feature = "metrics")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The kinds of operations being counted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Operation {
+    Encrypt,
+    Reencrypt,
+    Decrypt,
+    Verify,
+}
+
+/// Records the operation with the given outcome, and passes the outcome through.
+#[inline]
+pub(crate) fn record<T, E>(operation: Operation, result: Result<T, E>) -> Result<T, E> {
+    record_outcome(operation, result.is_ok());
+    result
+}
+
+/// Records the operation with the given outcome.
+#[inline]
+pub(crate) fn record_outcome(operation: Operation, success: bool) {
+    #[cfg(feature = "metrics")]
+    counter(operation).record(success);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = (operation, success);
+}
+
+/// The number of times an operation was performed.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OperationCounts {
+    /// The total number of calls, including the failed ones.
+    pub total: usize,
+    /// The number of calls that returned an error.
+    pub failed: usize,
+}
+
+/// A snapshot of the operation counters.
+///
+/// The counters are updated independently, so a snapshot taken
+/// while other threads are performing operations may be slightly inconsistent.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Metrics {
+    /// Encryptions (all the variants of `encrypt()` and `encrypt_record()`).
+    pub encrypt: OperationCounts,
+    /// Reencryptions (they never fail).
+    pub reencrypt: OperationCounts,
+    /// Decryptions (all the variants of `decrypt_original()` and `decrypt_reencrypted()`,
+    /// and `decrypt_combined()`).
+    pub decrypt: OperationCounts,
+    /// Verifications of key fragments and capsule fragments
+    /// (including `CapsuleFrag::verify_against_commitment()`
+    /// and `CapsuleFrag::verify_signature_only()`).
+    pub verify: OperationCounts,
+}
+
+#[cfg(feature = "metrics")]
+struct Counter {
+    total: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+#[cfg(feature = "metrics")]
+impl Counter {
+    const fn new() -> Self {
+        Self {
+            total: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+        }
+    }
+
+    fn record(&self, success: bool) {
+        // The counters are not used for synchronization, so the relaxed ordering is enough.
+        self.total.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn get(&self) -> OperationCounts {
+        OperationCounts {
+            total: self.total.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.total.store(0, Ordering::Relaxed);
+        self.failed.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "metrics")]
+static ENCRYPT: Counter = Counter::new();
+#[cfg(feature = "metrics")]
+static REENCRYPT: Counter = Counter::new();
+#[cfg(feature = "metrics")]
+static DECRYPT: Counter = Counter::new();
+#[cfg(feature = "metrics")]
+static VERIFY: Counter = Counter::new();
+
+#[cfg(feature = "metrics")]
+fn counter(operation: Operation) -> &'static Counter {
+    match operation {
+        Operation::Encrypt => &ENCRYPT,
+        Operation::Reencrypt => &REENCRYPT,
+        Operation::Decrypt => &DECRYPT,
+        Operation::Verify => &VERIFY,
+    }
+}
+
+/// Returns the current values of the operation counters
+/// (accumulated since the start of the process or the last [`reset_metrics`] call).
+#[cfg(feature = "metrics")]
+pub fn metrics() -> Metrics {
+    Metrics {
+        encrypt: ENCRYPT.get(),
+        reencrypt: REENCRYPT.get(),
+        decrypt: DECRYPT.get(),
+        verify: VERIFY.get(),
+    }
+}
+
+/// Sets all the operation counters to zero.
+#[cfg(feature = "metrics")]
+pub fn reset_metrics() {
+    ENCRYPT.reset();
+    REENCRYPT.reset();
+    DECRYPT.reset();
+    VERIFY.reset();
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+
+    use super::{metrics, Counter, OperationCounts};
+    use crate::{
+        decrypt_original, encrypt, generate_kfrags, reencrypt, DeserializableFromArray, KeyFrag,
+        SecretKey, SerializableToArray, Signer,
+    };
+
+    #[test]
+    fn test_counter() {
+        let counter = Counter::new();
+        counter.record(true);
+        counter.record(false);
+        counter.record(true);
+        assert_eq!(
+            counter.get(),
+            OperationCounts {
+                total: 3,
+                failed: 1
+            }
+        );
+
+        counter.reset();
+        assert_eq!(counter.get(), OperationCounts::default());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+        let receiving_pk = SecretKey::random().public_key();
+        let signer = Signer::new(&SecretKey::random());
+        let verifying_pk = signer.verifying_key();
+
+        // Other tests may be running concurrently, so only the lower bounds can be checked.
+        let before = metrics();
+
+        let (capsule, ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+        decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert!(decrypt_original(&SecretKey::random(), &capsule, &ciphertext).is_err());
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 1, 1, true, true);
+        let kfrag = KeyFrag::from_array(&kfrags[0].to_array()).unwrap();
+        let verified_kfrag = kfrag
+            .verify(&verifying_pk, Some(&delegating_pk), Some(&receiving_pk))
+            .unwrap();
+        assert!(kfrag.verify(&verifying_pk, None, None).is_err());
+        let cfrag = reencrypt(&capsule, &verified_kfrag).unverify();
+        assert!(cfrag.verify_signature_only(&verifying_pk, &delegating_pk, &receiving_pk));
+        assert!(!cfrag.verify_signature_only(&delegating_pk, &delegating_pk, &receiving_pk));
+
+        let after = metrics();
+        assert!(after.encrypt.total > before.encrypt.total);
+        assert!(after.decrypt.total >= before.decrypt.total + 2);
+        assert!(after.decrypt.failed > before.decrypt.failed);
+        assert!(after.verify.total >= before.verify.total + 4);
+        assert!(after.verify.failed >= before.verify.failed + 2);
+        assert!(after.reencrypt.total > before.reencrypt.total);
+    }
+}
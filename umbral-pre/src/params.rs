@@ -1,5 +1,13 @@
-use crate::curve::CurvePoint;
-use crate::hashing::unsafe_hash_to_point;
+use alloc::vec::Vec;
+use core::cmp::min;
+
+use digest::Digest;
+use typenum::Unsigned;
+
+use crate::curve::{CurvePoint, CurveScalar, BACKEND_NAME, CURVE_NAME};
+use crate::dem::{DemKeySize, DEM_NAME, KDF_NAME};
+use crate::hashing::{unsafe_hash_to_point, BackendDigest, BACKEND_DIGEST_NAME};
+use crate::traits::RepresentableAsArray;
 
 /// An object containing shared scheme parameters.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -28,10 +36,81 @@ impl Parameters {
     }
 }
 
+/// A description of the cryptographic primitives used by the library,
+/// and the security level they provide.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SecurityParameters {
+    /// The elliptic curve used for the keys and the KEM.
+    pub curve: &'static str,
+    /// The size of the curve order, in bits.
+    pub curve_order_bits: usize,
+    /// The hash function used for internal hashing and signatures.
+    pub hash: &'static str,
+    /// The output size of the hash function, in bits.
+    pub hash_output_bits: usize,
+    /// The AEAD scheme used for the DEM.
+    pub aead: &'static str,
+    /// The key size of the AEAD scheme, in bits.
+    pub aead_key_bits: usize,
+    /// The KDF used to derive DEM keys.
+    pub kdf: &'static str,
+    /// The approximate security level of the whole scheme, in bits
+    /// (the minimum of the security levels of the primitives).
+    pub security_bits: usize,
+}
+
+/// Returns the description of the cryptographic primitives the library was built with.
+pub fn security_parameters() -> SecurityParameters {
+    let curve_order_bits = <CurveScalar as RepresentableAsArray>::Size::to_usize() * 8;
+    let hash_output_bits = <BackendDigest as Digest>::OutputSize::to_usize() * 8;
+    let aead_key_bits = DemKeySize::to_usize() * 8;
+
+    // The best known attacks on the discrete logarithm problem (Pollard's rho)
+    // and on the hash collision resistance (birthday attack) are square-root ones.
+    let security_bits = min(
+        min(curve_order_bits / 2, hash_output_bits / 2),
+        aead_key_bits,
+    );
+
+    SecurityParameters {
+        curve: CURVE_NAME,
+        curve_order_bits,
+        hash: BACKEND_DIGEST_NAME,
+        hash_output_bits,
+        aead: DEM_NAME,
+        aead_key_bits,
+        kdf: KDF_NAME,
+        security_bits,
+    }
+}
+
+/// Returns the name of the elliptic curve arithmetic backend the library was built with.
+///
+/// Currently there is only one backend (`"k256"`, a constant-time pure Rust implementation),
+/// so there is nothing to choose from at runtime.
+pub fn backend_name() -> &'static str {
+    BACKEND_NAME
+}
+
+const FEATURES: &[(&str, bool)] = &[
+    ("default-rng", cfg!(feature = "default-rng")),
+    ("bench-internals", cfg!(feature = "bench-internals")),
+    ("metrics", cfg!(feature = "metrics")),
+];
+
+/// Returns the names of the optional Cargo features the library was built with.
+pub fn features() -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .filter(|(_name, enabled)| *enabled)
+        .map(|(name, _enabled)| *name)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::Parameters;
+    use super::{backend_name, features, security_parameters, Parameters};
 
     #[test]
     fn test_default() {
@@ -39,4 +118,33 @@ mod tests {
         let p2 = Parameters::new();
         assert_eq!(p1, p2);
     }
+
+    #[test]
+    fn test_security_parameters() {
+        let params = security_parameters();
+        assert_eq!(params.curve, "secp256k1");
+        assert_eq!(params.curve_order_bits, 256);
+        assert_eq!(params.hash_output_bits, 256);
+        assert_eq!(params.aead_key_bits, 256);
+        assert_eq!(params.security_bits, 128);
+    }
+
+    #[test]
+    fn test_backend_name() {
+        assert_eq!(backend_name(), "k256");
+    }
+
+    #[test]
+    fn test_features() {
+        let features = features();
+        assert_eq!(
+            features.contains(&"default-rng"),
+            cfg!(feature = "default-rng")
+        );
+        assert_eq!(
+            features.contains(&"bench-internals"),
+            cfg!(feature = "bench-internals")
+        );
+        assert_eq!(features.contains(&"metrics"), cfg!(feature = "metrics"));
+    }
 }
@@ -7,14 +7,17 @@ use rand_core::{CryptoRng, RngCore};
 #[cfg(feature = "default-rng")]
 use rand_core::OsRng;
 
-use crate::capsule::{Capsule, OpenReencryptedError};
-use crate::capsule_frag::VerifiedCapsuleFrag;
+use crate::capsule::{common_precursor, Capsule, OpenReencryptedError};
+use crate::capsule_frag::{CapsuleFragVerifier, VerifiedCapsuleFrag};
+use crate::combination::CombinedCapsuleFrags;
 use crate::dem::{DecryptionError, EncryptionError, DEM};
-use crate::key_frag::{KeyFragBase, VerifiedKeyFrag};
+use crate::key_frag::{check_kfrags_consistency, KeyFragBase, KeyFragID, VerifiedKeyFrag};
 use crate::keys::{PublicKey, SecretKey, Signer};
+use crate::metrics::{record, record_outcome, Operation};
 use crate::traits::SerializableToArray;
 
 use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 
 /// Errors that can happen when decrypting a reencrypted ciphertext.
@@ -35,6 +38,22 @@ impl fmt::Display for ReencryptionError {
     }
 }
 
+/// Errors that can happen when generating key fragments for the given labels.
+#[derive(Debug, PartialEq)]
+pub enum LabeledKeyFragsError {
+    /// Some of the given labels are repeated
+    /// (the fragments would have the same IDs and could not be used together).
+    RepeatingLabels,
+}
+
+impl fmt::Display for LabeledKeyFragsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RepeatingLabels => write!(f, "Labels must be distinct"),
+        }
+    }
+}
+
 /// Encrypts the given plaintext message using a DEM scheme,
 /// and encapsulates the key for later reencryption.
 /// Returns the KEM [`Capsule`] and the ciphertext.
@@ -42,6 +61,20 @@ pub fn encrypt_with_rng(
     rng: &mut (impl CryptoRng + RngCore),
     delegating_pk: &PublicKey,
     plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    record(
+        Operation::Encrypt,
+        encrypt_impl(rng, delegating_pk, plaintext),
+    )
+}
+
+// The uncounted versions of the public functions, for the checks performing operations
+// internally (so that they do not show up in the operation counters).
+
+fn encrypt_impl(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
 ) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
     let (capsule, key_seed) = Capsule::from_public_key(rng, delegating_pk);
     let dem = DEM::new(&key_seed);
@@ -49,6 +82,38 @@ pub fn encrypt_with_rng(
         .map(|ciphertext| (capsule, ciphertext))
 }
 
+fn decrypt_original_impl(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    let key_seed = capsule.open_original(delegating_sk);
+    let dem = DEM::new(&key_seed);
+    dem.decrypt(ciphertext, &capsule.to_array())
+}
+
+fn decrypt_reencrypted_impl(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, ReencryptionError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    capsule
+        .open_reencrypted(receiving_sk, delegating_pk, &cfrags)
+        .map_err(ReencryptionError::OnOpen)
+        .and_then(|key_seed| {
+            let dem = DEM::new(&key_seed);
+            dem.decrypt(&ciphertext, &capsule.to_array())
+                .map_err(ReencryptionError::OnDecryption)
+        })
+}
+
 /// A synonym for [`encrypt`] with the default RNG.
 #[cfg(feature = "default-rng")]
 pub fn encrypt(
@@ -58,15 +123,155 @@ pub fn encrypt(
     encrypt_with_rng(&mut OsRng, delegating_pk, plaintext)
 }
 
+/// Same as [`encrypt_with_rng`], but returns the authentication tag (16 bytes)
+/// separately from the rest of the ciphertext, for storage systems that keep the tag apart.
+/// Returns the KEM [`Capsule`], the ciphertext without the tag, and the tag.
+///
+/// The concatenation of the ciphertext and the tag is the same as the ciphertext
+/// returned by [`encrypt`], so it can be decrypted with the regular functions as well.
+#[allow(clippy::type_complexity)]
+pub fn encrypt_detached_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>, Box<[u8]>), EncryptionError> {
+    let (capsule, key_seed) = Capsule::from_public_key(rng, delegating_pk);
+    let dem = DEM::new(&key_seed);
+    let result = dem
+        .encrypt_detached(rng, plaintext, &capsule.to_array())
+        .map(|(ciphertext_body, tag)| (capsule, ciphertext_body, tag));
+    record(Operation::Encrypt, result)
+}
+
+/// A synonym for [`encrypt_detached_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+#[allow(clippy::type_complexity)]
+pub fn encrypt_detached(
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>, Box<[u8]>), EncryptionError> {
+    encrypt_detached_with_rng(&mut OsRng, delegating_pk, plaintext)
+}
+
 /// Attempts to decrypt the ciphertext using the receiver's secret key.
 pub fn decrypt_original(
     delegating_sk: &SecretKey,
     capsule: &Capsule,
     ciphertext: impl AsRef<[u8]>,
 ) -> Result<Box<[u8]>, DecryptionError> {
+    record(
+        Operation::Decrypt,
+        decrypt_original_impl(delegating_sk, capsule, ciphertext),
+    )
+}
+
+/// Same as [`decrypt_original`], but writes the plaintext into the beginning of `out`
+/// instead of allocating it, and returns its size.
+///
+/// The plaintext size can be found in advance with [`plaintext_size`];
+/// if `out` is shorter, [`DecryptionError::OutputBufferTooSmall`] is returned.
+pub fn decrypt_original_into(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+    out: &mut [u8],
+) -> Result<usize, DecryptionError> {
     let key_seed = capsule.open_original(delegating_sk);
     let dem = DEM::new(&key_seed);
-    dem.decrypt(ciphertext, &capsule.to_array())
+    record(
+        Operation::Decrypt,
+        dem.decrypt_into(ciphertext, &capsule.to_array(), out),
+    )
+}
+
+/// Same as [`decrypt_original`], for the ciphertext and the tag
+/// produced by [`encrypt_detached`].
+///
+/// Returns [`DecryptionError::InvalidTagSize`] if the tag has an incorrect size,
+/// and [`DecryptionError::AuthenticationFailed`] if it does not match the ciphertext.
+pub fn decrypt_original_detached(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext_body: impl AsRef<[u8]>,
+    tag: &[u8],
+) -> Result<Box<[u8]>, DecryptionError> {
+    let key_seed = capsule.open_original(delegating_sk);
+    let dem = DEM::new(&key_seed);
+    record(
+        Operation::Decrypt,
+        dem.decrypt_detached(ciphertext_body, tag, &capsule.to_array()),
+    )
+}
+
+/// Returns the size of the plaintext encrypted in a ciphertext of the given size
+/// by [`encrypt`], or `None` if the ciphertext is too short to be valid.
+pub fn plaintext_size(ciphertext_size: usize) -> Option<usize> {
+    DEM::plaintext_size(ciphertext_size)
+}
+
+/// Returns `true` if `delegating_sk` is the key the capsule was created for,
+/// that is, [`decrypt_original`] (and not [`decrypt_reencrypted`]) should be used with it.
+///
+/// A capsule by itself is not bound to the public key it was created for,
+/// so the check requires the ciphertext and consists of authenticating it
+/// with the key opened by `delegating_sk`.
+/// Therefore it costs about as much as the decryption itself.
+pub fn is_owner(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> bool {
+    decrypt_original_impl(delegating_sk, capsule, ciphertext).is_ok()
+}
+
+/// Encrypts a random plaintext for the public key of `delegating_sk`
+/// and decrypts it with `delegating_sk`, returning `true` if the plaintext is recovered.
+///
+/// A health check for a newly provisioned key, covering the direct use
+/// ([`encrypt`] followed by [`decrypt_original`]); see [`validate_grant`]
+/// for checking a delegation.
+pub fn self_encrypt_decrypt_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_sk: &SecretKey,
+) -> bool {
+    let mut plaintext = [0u8; 32];
+    rng.fill_bytes(&mut plaintext);
+
+    let (capsule, ciphertext) = match encrypt_impl(rng, &delegating_sk.public_key(), &plaintext) {
+        Ok(result) => result,
+        Err(_) => return false,
+    };
+
+    decrypt_original_impl(delegating_sk, &capsule, &ciphertext)
+        .map(|decrypted| decrypted.as_ref() == plaintext)
+        .unwrap_or(false)
+}
+
+/// A synonym for [`self_encrypt_decrypt_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+pub fn self_encrypt_decrypt(delegating_sk: &SecretKey) -> bool {
+    self_encrypt_decrypt_with_rng(&mut OsRng, delegating_sk)
+}
+
+/// Returns `true` if two encryption results (as returned by [`encrypt`]) share the same capsule.
+///
+/// This is what can be checked about two ciphertexts without any keys.
+/// A capsule is created anew on every encryption, so equal capsules mean that
+/// the ciphertexts were produced by the same encryption (or that the capsule was reused),
+/// and that anyone able to decrypt one of them can decrypt the other one.
+/// If the ciphertexts are byte-for-byte equal as well, they decrypt to the same plaintext.
+///
+/// What cannot be checked without the key:
+/// - whether two different ciphertexts (even with the same capsule) decrypt to the same plaintext:
+///   the nonce and the authentication tag do not determine the plaintext;
+/// - whether two encryptions with different capsules contain the same plaintext
+///   (they never share the key or the ciphertext);
+/// - whether a ciphertext is authentic: the capsule is authenticated along with the ciphertext,
+///   but a modification or a mismatched capsule is only detected on decryption.
+pub fn same_capsule(capsule_a: &Capsule, capsule_b: &Capsule) -> bool {
+    // Capsule equality compares the normalized points,
+    // so it does not depend on how the capsules were obtained.
+    capsule_a == capsule_b
 }
 
 /// Creates `shares` fragments of `delegating_sk`,
@@ -75,6 +280,9 @@ pub fn decrypt_original(
 ///
 /// `threshold` sets the number of fragments necessary for decryption
 /// (that is, fragments created with `threshold > num_frags` will be useless).
+/// There is no upper limit on `threshold` and `shares` besides the computational cost:
+/// the generation takes `O(threshold * shares)` time,
+/// and the decryption with `threshold` fragments takes `O(threshold^2)` time.
 ///
 /// `signer` is used to sign the resulting [`KeyFrag`](`crate::KeyFrag`) objects,
 /// which can be later verified by the associated public key.
@@ -135,6 +343,276 @@ pub fn generate_kfrags(
     )
 }
 
+/// Same as [`generate_kfrags_with_rng`], but instead of being random,
+/// the IDs of the resulting key fragments are derived from `id_seed` and `labels`.
+/// One fragment is created for each label, in the same order.
+///
+/// This allows one to re-create a delegation (e.g., after a key rotation)
+/// and have the fragments with the same IDs assigned to the same labels.
+///
+/// **Warning:** kfrag IDs are public, and are included in the resulting capsule fragments.
+/// With a fixed `id_seed`, fragments of all delegations made with the same label
+/// will have the same ID, making it possible to link them together,
+/// and anyone who knows `id_seed` will be able to predict the IDs.
+/// Use a dedicated random `id_seed` per delegation
+/// unless this correlation is specifically desired.
+/// The values of key shares are not affected,
+/// since they also depend on the ephemeral secret of each delegation.
+///
+/// The labels must be distinct, since fragments with equal IDs cannot be used together
+/// for decryption; otherwise [`LabeledKeyFragsError::RepeatingLabels`] is returned.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_labeled_kfrags_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signer: &Signer,
+    threshold: usize,
+    id_seed: &[u8],
+    labels: &[impl AsRef<[u8]>],
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+) -> Result<Box<[VerifiedKeyFrag]>, LabeledKeyFragsError> {
+    check_labels_distinct(labels)?;
+
+    let base = KeyFragBase::new(rng, delegating_sk, receiving_pk, signer, threshold);
+
+    let mut result = Vec::<VerifiedKeyFrag>::new();
+    for label in labels {
+        result.push(VerifiedKeyFrag::from_base_with_id(
+            rng,
+            &base,
+            KeyFragID::from_label(id_seed, label.as_ref()),
+            sign_delegating_key,
+            sign_receiving_key,
+        ));
+    }
+
+    Ok(result.into_boxed_slice())
+}
+
+/// A synonym for [`generate_labeled_kfrags_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_labeled_kfrags(
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signer: &Signer,
+    threshold: usize,
+    id_seed: &[u8],
+    labels: &[impl AsRef<[u8]>],
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+) -> Result<Box<[VerifiedKeyFrag]>, LabeledKeyFragsError> {
+    generate_labeled_kfrags_with_rng(
+        &mut OsRng,
+        delegating_sk,
+        receiving_pk,
+        signer,
+        threshold,
+        id_seed,
+        labels,
+        sign_delegating_key,
+        sign_receiving_key,
+    )
+}
+
+fn check_labels_distinct(labels: &[impl AsRef<[u8]>]) -> Result<(), LabeledKeyFragsError> {
+    let unique_labels: BTreeSet<&[u8]> = labels.iter().map(|label| label.as_ref()).collect();
+    if unique_labels.len() != labels.len() {
+        return Err(LabeledKeyFragsError::RepeatingLabels);
+    }
+    Ok(())
+}
+
+/// Same as [`generate_labeled_kfrags_with_rng`], but the whole delegation
+/// (the precursor, and the generating polynomial along with it) is derived deterministically
+/// from `delegating_sk`, `receiving_pk`, `threshold` and `delegation_seed`,
+/// the latter also being used as the seed for the kfrag IDs.
+/// `rng` is only used for the signatures.
+///
+/// This allows the delegator to regenerate the fragments for some of the labels
+/// (e.g., if one of them is lost) without affecting the shares of the others:
+/// calling this function again with the same `delegating_sk`, `receiving_pk`, `threshold`
+/// and `delegation_seed`, and a subset of the original labels, results in fragments
+/// with the same IDs and key shares as the original ones for these labels,
+/// consistent with the rest of the delegation.
+/// Only the signatures will differ.
+///
+/// The constraints are:
+/// - the threshold must be the same as in the original delegation,
+///   otherwise the result is a different delegation
+///   (rejected by [`check_kfrags_consistency`](`crate::check_kfrags_consistency`)
+///   when mixed with the original fragments);
+/// - since the delegation is tied to `delegation_seed`, a new delegation to the same receiver
+///   (e.g., replacing a revoked one) must use a new seed, otherwise it will be
+///   identical to the old one;
+/// - as with [`generate_labeled_kfrags_with_rng`], the IDs, and therefore the fragments
+///   made for the same label, can be linked by anyone who knows `delegation_seed`.
+///   The secret values cannot be derived without `delegating_sk`.
+///
+/// As with [`generate_labeled_kfrags_with_rng`], the labels must be distinct.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_deterministic_kfrags_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signer: &Signer,
+    threshold: usize,
+    delegation_seed: &[u8],
+    labels: &[impl AsRef<[u8]>],
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+) -> Result<Box<[VerifiedKeyFrag]>, LabeledKeyFragsError> {
+    check_labels_distinct(labels)?;
+
+    let base = KeyFragBase::new_deterministic(
+        delegating_sk,
+        receiving_pk,
+        signer,
+        threshold,
+        delegation_seed,
+    );
+
+    let mut result = Vec::<VerifiedKeyFrag>::new();
+    for label in labels {
+        result.push(VerifiedKeyFrag::from_base_with_id(
+            rng,
+            &base,
+            KeyFragID::from_label(delegation_seed, label.as_ref()),
+            sign_delegating_key,
+            sign_receiving_key,
+        ));
+    }
+
+    Ok(result.into_boxed_slice())
+}
+
+/// A synonym for [`generate_deterministic_kfrags_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_deterministic_kfrags(
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signer: &Signer,
+    threshold: usize,
+    delegation_seed: &[u8],
+    labels: &[impl AsRef<[u8]>],
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+) -> Result<Box<[VerifiedKeyFrag]>, LabeledKeyFragsError> {
+    generate_deterministic_kfrags_with_rng(
+        &mut OsRng,
+        delegating_sk,
+        receiving_pk,
+        signer,
+        threshold,
+        delegation_seed,
+        labels,
+        sign_delegating_key,
+        sign_receiving_key,
+    )
+}
+
+/// Checks locally that the key fragments make up a working delegation
+/// from `delegating_sk` to `receiving_pk` signed by `signer` with the given `threshold`,
+/// before they are distributed.
+///
+/// A test plaintext is encrypted for `delegating_sk`, and the resulting capsule
+/// is reencrypted with each of the key fragments. The check passes if:
+/// - there are at least `threshold` (and at least one) distinct key fragments
+///   of the same delegation (see [`check_kfrags_consistency`](`crate::check_kfrags_consistency`));
+/// - each of them is signed by `signer` for `delegating_sk` and `receiving_pk`;
+/// - each of the resulting capsule fragments passes the verification.
+///
+/// The decryption requires the receiving secret key, which the delegating party
+/// does not normally have. If it is given as `maybe_receiving_sk`,
+/// it must correspond to `receiving_pk`, and the check additionally requires
+/// the test plaintext to be recovered from the first `threshold` capsule fragments and from all of them,
+/// and not to be recovered from `threshold - 1` of them (that is, the threshold is exactly the given one).
+#[allow(clippy::too_many_arguments)]
+pub fn validate_grant_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    maybe_receiving_sk: Option<&SecretKey>,
+    signer: &Signer,
+    kfrags: &[VerifiedKeyFrag],
+    threshold: usize,
+) -> bool {
+    if threshold == 0 || kfrags.len() < threshold || check_kfrags_consistency(kfrags).is_err() {
+        return false;
+    }
+
+    let delegating_pk = delegating_sk.public_key();
+    let verifying_pk = signer.verifying_key();
+
+    if !kfrags.iter().all(|vkfrag| {
+        vkfrag
+            .kfrag
+            .verify_impl(&verifying_pk, Some(&delegating_pk), Some(receiving_pk))
+            .is_ok()
+    }) {
+        return false;
+    }
+
+    let plaintext = b"GRANT_VALIDATION";
+    let (capsule, ciphertext) = match encrypt_impl(rng, &delegating_pk, plaintext) {
+        Ok(result) => result,
+        Err(_) => return false,
+    };
+
+    let verifier = CapsuleFragVerifier::new(&capsule, &verifying_pk, &delegating_pk, receiving_pk);
+    let mut vcfrags = Vec::<VerifiedCapsuleFrag>::with_capacity(kfrags.len());
+    for vkfrag in kfrags {
+        let vcfrag = VerifiedCapsuleFrag::reencrypted(rng, &capsule, &vkfrag.kfrag);
+        if verifier.verify_impl(&vcfrag.cfrag).is_err() {
+            return false;
+        }
+        vcfrags.push(vcfrag);
+    }
+
+    let receiving_sk = match maybe_receiving_sk {
+        Some(receiving_sk) => receiving_sk,
+        None => return true,
+    };
+
+    if &receiving_sk.public_key() != receiving_pk {
+        return false;
+    }
+
+    let decrypts = |vcfrags: &[VerifiedCapsuleFrag]| {
+        decrypt_reencrypted_impl(receiving_sk, &delegating_pk, &capsule, vcfrags, &ciphertext)
+            .map(|decrypted| decrypted.as_ref() == plaintext)
+            .unwrap_or(false)
+    };
+
+    decrypts(&vcfrags[..threshold])
+        && decrypts(&vcfrags)
+        && (threshold == 1 || !decrypts(&vcfrags[..threshold - 1]))
+}
+
+/// A synonym for [`validate_grant_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+pub fn validate_grant(
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    maybe_receiving_sk: Option<&SecretKey>,
+    signer: &Signer,
+    kfrags: &[VerifiedKeyFrag],
+    threshold: usize,
+) -> bool {
+    validate_grant_with_rng(
+        &mut OsRng,
+        delegating_sk,
+        receiving_pk,
+        maybe_receiving_sk,
+        signer,
+        kfrags,
+        threshold,
+    )
+}
+
 /// Reencrypts a [`Capsule`] object with a key fragment, creating a capsule fragment.
 ///
 /// Having `threshold` (see [`generate_kfrags()`](`crate::generate_kfrags()`))
@@ -148,7 +626,9 @@ pub fn reencrypt_with_rng(
     capsule: &Capsule,
     verified_kfrag: &VerifiedKeyFrag,
 ) -> VerifiedCapsuleFrag {
-    VerifiedCapsuleFrag::reencrypted(rng, capsule, &verified_kfrag.kfrag)
+    let verified_cfrag = VerifiedCapsuleFrag::reencrypted(rng, capsule, &verified_kfrag.kfrag);
+    record_outcome(Operation::Reencrypt, true);
+    verified_cfrag
 }
 
 /// A synonym for [`reencrypt_with_rng`] with the default RNG.
@@ -164,9 +644,25 @@ pub fn reencrypt(capsule: &Capsule, verified_kfrag: &VerifiedKeyFrag) -> Verifie
 ///
 /// `delegating_pk` is the public key of the encrypting party.
 /// Used to check the validity of decryption.
+/// It cannot be made optional: the capsule does not commit to it
+/// (see [`Capsule`]), and the only way to recover it from the capsule fragments
+/// would make this check vacuous, so that a wrong result would only be detected
+/// by the ciphertext authentication, without the reason.
+/// It is also required to verify the fragments in the first place,
+/// so it is already known by the time of decryption.
 ///
 /// One can call [`CapsuleFrag::verify()`](`crate::CapsuleFrag::verify`)
 /// before reencryption to check its integrity.
+///
+/// All the given capsule fragments are used in the decryption,
+/// so any number of distinct fragments of the same delegation
+/// starting from the threshold can be passed (there is no need to select a subset).
+/// Given fewer fragments than the threshold, the decryption fails with
+/// [`OpenReencryptedError::ValidationFailed`](`crate::OpenReencryptedError::ValidationFailed`)
+/// (the threshold is not stored in the fragments, so it cannot be detected earlier).
+/// An empty list results in [`OpenReencryptedError::NoCapsuleFrags`](`crate::OpenReencryptedError::NoCapsuleFrags`),
+/// and fragments from different delegations in
+/// [`OpenReencryptedError::MismatchedCapsuleFrags`](`crate::OpenReencryptedError::MismatchedCapsuleFrags`).
 pub fn decrypt_reencrypted(
     receiving_sk: &SecretKey,
     delegating_pk: &PublicKey,
@@ -174,32 +670,140 @@ pub fn decrypt_reencrypted(
     verified_cfrags: &[VerifiedCapsuleFrag],
     ciphertext: impl AsRef<[u8]>,
 ) -> Result<Box<[u8]>, ReencryptionError> {
+    record(
+        Operation::Decrypt,
+        decrypt_reencrypted_impl(
+            receiving_sk,
+            delegating_pk,
+            capsule,
+            verified_cfrags,
+            ciphertext,
+        ),
+    )
+}
+
+/// Same as [`decrypt_reencrypted`], but writes the plaintext into the beginning of `out`
+/// instead of allocating it, and returns its size.
+///
+/// The plaintext size can be found in advance with [`plaintext_size`];
+/// if `out` is shorter, [`DecryptionError::OutputBufferTooSmall`] is returned.
+pub fn decrypt_reencrypted_into(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    ciphertext: impl AsRef<[u8]>,
+    out: &mut [u8],
+) -> Result<usize, ReencryptionError> {
     let cfrags: Vec<_> = verified_cfrags
         .iter()
         .cloned()
         .map(|vcfrag| vcfrag.cfrag)
         .collect();
-    let key_seed = capsule
+    let result = capsule
         .open_reencrypted(receiving_sk, delegating_pk, &cfrags)
-        .map_err(ReencryptionError::OnOpen)?;
-    let dem = DEM::new(&key_seed);
-    dem.decrypt(&ciphertext, &capsule.to_array())
-        .map_err(ReencryptionError::OnDecryption)
+        .map_err(ReencryptionError::OnOpen)
+        .and_then(|key_seed| {
+            let dem = DEM::new(&key_seed);
+            dem.decrypt_into(&ciphertext, &capsule.to_array(), out)
+                .map_err(ReencryptionError::OnDecryption)
+        });
+    record(Operation::Decrypt, result)
 }
 
-#[cfg(test)]
-mod tests {
-
-    use alloc::vec::Vec;
-
-    use crate::{
-        CapsuleFrag, DeserializableFromArray, KeyFrag, SecretKey, SerializableToArray, Signer,
-        VerifiedCapsuleFrag,
-    };
-
-    use super::{decrypt_original, decrypt_reencrypted, encrypt, generate_kfrags, reencrypt};
+/// Same as [`decrypt_reencrypted`], for the ciphertext and the tag
+/// produced by [`encrypt_detached`].
+///
+/// See [`decrypt_original_detached`] for the tag-related errors.
+pub fn decrypt_reencrypted_detached(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    ciphertext_body: impl AsRef<[u8]>,
+    tag: &[u8],
+) -> Result<Box<[u8]>, ReencryptionError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    let result = capsule
+        .open_reencrypted(receiving_sk, delegating_pk, &cfrags)
+        .map_err(ReencryptionError::OnOpen)
+        .and_then(|key_seed| {
+            let dem = DEM::new(&key_seed);
+            dem.decrypt_detached(&ciphertext_body, tag, &capsule.to_array())
+                .map_err(ReencryptionError::OnDecryption)
+        });
+    record(Operation::Decrypt, result)
+}
 
-    #[test]
+/// Decrypts the ciphertext using capsule fragments combined by
+/// [`combine_cfrags()`](`crate::combine_cfrags()`).
+///
+/// This is the final step of a decryption where the combination of capsule fragments
+/// is offloaded to a helper; see [`combination_coefficients()`](`crate::combination_coefficients()`)
+/// for the first step.
+///
+/// `verified_cfrags` must be the receiver's own copy of the fragments
+/// given to the helper; the result of the combination is checked against them.
+pub fn decrypt_combined(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    combined_cfrags: &CombinedCapsuleFrags,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, ReencryptionError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    let pub_key = receiving_sk.public_key().to_point();
+    let result = common_precursor(&cfrags)
+        .and_then(|precursor| {
+            let dh_point = &precursor * receiving_sk.to_secret_scalar().as_secret();
+            capsule.open_combined(
+                delegating_pk,
+                &precursor,
+                &pub_key,
+                &dh_point,
+                &combined_cfrags.point_e,
+                &combined_cfrags.point_v,
+            )
+        })
+        .map_err(ReencryptionError::OnOpen)
+        .and_then(|key_seed| {
+            let dem = DEM::new(&key_seed);
+            dem.decrypt(&ciphertext, &capsule.to_array())
+                .map_err(ReencryptionError::OnDecryption)
+        });
+    record(Operation::Decrypt, result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use crate::bench::DummyRng;
+    use crate::{
+        check_kfrags_consistency, delegations_equal, Capsule, CapsuleFrag, DecryptionError,
+        DeserializableFromArray, KeyFrag, KeyFragSetError, OpenReencryptedError, SecretKey,
+        SerializableToArray, SerializableToSecretArray, Signer, VerifiedCapsuleFrag,
+    };
+
+    use super::{
+        decrypt_original, decrypt_original_detached, decrypt_original_into, decrypt_reencrypted,
+        decrypt_reencrypted_detached, decrypt_reencrypted_into, encrypt, encrypt_detached,
+        generate_deterministic_kfrags, generate_kfrags, generate_labeled_kfrags, is_owner,
+        plaintext_size, reencrypt, same_capsule, self_encrypt_decrypt,
+        self_encrypt_decrypt_with_rng, validate_grant, LabeledKeyFragsError, ReencryptionError,
+    };
+
+    #[test]
     fn test_simple_api() {
         /*
         This test models the main interactions between NuCypher actors (i.e., Alice,
@@ -265,7 +869,7 @@ mod tests {
 
         let verified_cfrags: Vec<VerifiedCapsuleFrag> = verified_kfrags[0..threshold]
             .iter()
-            .map(|vkfrag| reencrypt(&capsule, &vkfrag))
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
             .collect();
 
         // Simulate network transfer
@@ -295,4 +899,744 @@ mod tests {
         .unwrap();
         assert_eq!(&plaintext_bob as &[u8], plaintext);
     }
+
+    #[test]
+    fn test_labeled_kfrags() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signing_sk = SecretKey::random();
+        let signer = Signer::new(&signing_sk);
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let labels = [&b"node-0"[..], b"node-1", b"node-2"];
+        let seed = b"delegation seed";
+
+        let kfrags = generate_labeled_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(kfrags.len(), labels.len());
+
+        // Re-generating the delegation for the same labels results in the same IDs
+        let kfrags_again = generate_labeled_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        for (kfrag, kfrag_again) in kfrags.iter().zip(kfrags_again.iter()) {
+            assert_eq!(kfrag.kfrag.id, kfrag_again.kfrag.id);
+            assert_ne!(kfrag.kfrag.key, kfrag_again.kfrag.key);
+        }
+
+        // A different seed results in different IDs
+        let kfrags_other_seed = generate_labeled_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            b"other seed",
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_ne!(kfrags[0].kfrag.id, kfrags_other_seed[0].kfrag.id);
+
+        // The fragments are fully functional
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+        let verified_cfrags: Vec<_> = kfrags[1..]
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
+            .collect();
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Repeated labels
+        let repeated_labels = [&b"node-0"[..], b"node-1", b"node-0"];
+        assert_eq!(
+            generate_labeled_kfrags(
+                &delegating_sk,
+                &receiving_pk,
+                &signer,
+                2,
+                seed,
+                &repeated_labels,
+                true,
+                true,
+            ),
+            Err(LabeledKeyFragsError::RepeatingLabels)
+        );
+    }
+
+    #[test]
+    fn test_is_owner() {
+        let delegating_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+
+        let (capsule, ciphertext) = encrypt(&delegating_sk.public_key(), b"peace at dawn").unwrap();
+
+        assert!(is_owner(&delegating_sk, &capsule, &ciphertext));
+        assert!(!is_owner(&receiving_sk, &capsule, &ciphertext));
+
+        // Tampered ciphertext
+        let mut tampered = ciphertext.to_vec();
+        tampered[0] ^= 1;
+        assert!(!is_owner(&delegating_sk, &capsule, &tampered));
+    }
+
+    #[test]
+    fn test_same_capsule() {
+        let delegating_pk = SecretKey::random().public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, _ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+        let capsule_back = Capsule::from_array(&capsule.to_array()).unwrap();
+        assert!(same_capsule(&capsule, &capsule_back));
+
+        // Encrypting the same plaintext again produces a different capsule
+        let (capsule_other, _ciphertext_other) = encrypt(&delegating_pk, plaintext).unwrap();
+        assert!(!same_capsule(&capsule, &capsule_other));
+    }
+
+    #[test]
+    fn test_receiver_mismatch() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let other_sk = SecretKey::random();
+
+        let (capsule, ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+        let kfrags = generate_kfrags(
+            &delegating_sk,
+            &receiving_sk.public_key(),
+            &signer,
+            2,
+            3,
+            true,
+            true,
+        );
+        let verified_cfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
+            .collect();
+
+        assert_eq!(
+            decrypt_reencrypted(
+                &other_sk,
+                &delegating_pk,
+                &capsule,
+                &verified_cfrags,
+                &ciphertext
+            ),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::ReceiverMismatch
+            ))
+        );
+
+        // A wrong delegating key is still reported as a validation failure
+        assert_eq!(
+            decrypt_reencrypted(
+                &receiving_sk,
+                &other_sk.public_key(),
+                &capsule,
+                &verified_cfrags,
+                &ciphertext
+            ),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::ValidationFailed
+            ))
+        );
+    }
+
+    #[test]
+    fn test_large_delegation() {
+        // There is no limit on the number of shares or the threshold
+        // (the kfrag IDs are 256-bit, so collisions are not a concern),
+        // only the computational cost grows. Make sure nothing breaks on larger values.
+        let threshold: usize = 100;
+        let shares: usize = threshold + 1;
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+        let verifying_pk = signer.verifying_key();
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+
+        let kfrags = generate_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            threshold,
+            shares,
+            true,
+            true,
+        );
+        assert_eq!(kfrags.len(), shares);
+
+        // Serialization round-trip for the whole delegation and the whole set of cfrags
+        let kfrags: Vec<_> = kfrags
+            .iter()
+            .map(|vkfrag| {
+                KeyFrag::from_array(&vkfrag.to_array())
+                    .unwrap()
+                    .verify(&verifying_pk, Some(&delegating_pk), Some(&receiving_pk))
+                    .unwrap()
+            })
+            .collect();
+
+        let verified_cfrags: Vec<_> = kfrags
+            .iter()
+            .map(|vkfrag| {
+                let vcfrag = reencrypt(&capsule, vkfrag);
+                CapsuleFrag::from_array(&vcfrag.to_array())
+                    .unwrap()
+                    .verify(&capsule, &verifying_pk, &delegating_pk, &receiving_pk)
+                    .unwrap()
+            })
+            .collect();
+
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags[1..],
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // One fragment short of the threshold
+        assert_eq!(
+            decrypt_reencrypted(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &verified_cfrags[2..],
+                &ciphertext,
+            ),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::ValidationFailed
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decrypt_into() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+
+        assert_eq!(plaintext_size(ciphertext.len()), Some(plaintext.len()));
+        assert_eq!(plaintext_size(10), None);
+
+        // The buffer can be larger than the plaintext
+        let mut out = [0u8; 32];
+        let size = decrypt_original_into(&delegating_sk, &capsule, &ciphertext, &mut out).unwrap();
+        assert_eq!(&out[..size], plaintext);
+
+        let mut out = [0u8; 12];
+        assert_eq!(
+            decrypt_original_into(&delegating_sk, &capsule, &ciphertext, &mut out),
+            Err(DecryptionError::OutputBufferTooSmall)
+        );
+
+        let mut tampered = ciphertext.to_vec();
+        tampered[30] ^= 1;
+        let mut out = [0u8; 32];
+        assert_eq!(
+            decrypt_original_into(&delegating_sk, &capsule, &tampered, &mut out),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+        assert_eq!(
+            decrypt_original_into(&delegating_sk, &capsule, &ciphertext[..30], &mut out),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+        assert_eq!(
+            decrypt_original_into(&delegating_sk, &capsule, &ciphertext[..10], &mut out),
+            Err(DecryptionError::CiphertextTooShort)
+        );
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let verified_cfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
+            .collect();
+
+        let mut out = [0u8; 13];
+        let size = decrypt_reencrypted_into(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags,
+            &ciphertext,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(&out[..size], plaintext);
+
+        let mut out = [0u8; 12];
+        assert_eq!(
+            decrypt_reencrypted_into(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &verified_cfrags,
+                &ciphertext,
+                &mut out,
+            ),
+            Err(ReencryptionError::OnDecryption(
+                DecryptionError::OutputBufferTooSmall
+            ))
+        );
+    }
+
+    #[test]
+    fn test_detached_tag() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext_body, tag) = encrypt_detached(&delegating_pk, plaintext).unwrap();
+        assert_eq!(tag.len(), 16);
+
+        let plaintext_back =
+            decrypt_original_detached(&delegating_sk, &capsule, &ciphertext_body, &tag).unwrap();
+        assert_eq!(&plaintext_back as &[u8], plaintext);
+
+        // The combined ciphertext is the concatenation of the body and the tag
+        let combined: Vec<u8> = ciphertext_body.iter().chain(tag.iter()).cloned().collect();
+        let plaintext_back = decrypt_original(&delegating_sk, &capsule, &combined).unwrap();
+        assert_eq!(&plaintext_back as &[u8], plaintext);
+
+        assert_eq!(
+            decrypt_original_detached(&delegating_sk, &capsule, &ciphertext_body, &tag[..15]),
+            Err(DecryptionError::InvalidTagSize)
+        );
+
+        let mut bad_tag = tag.to_vec();
+        bad_tag[0] ^= 1;
+        assert_eq!(
+            decrypt_original_detached(&delegating_sk, &capsule, &ciphertext_body, &bad_tag),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+
+        let mut bad_body = ciphertext_body.to_vec();
+        bad_body[30] ^= 1;
+        assert_eq!(
+            decrypt_original_detached(&delegating_sk, &capsule, &bad_body, &tag),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+
+        assert_eq!(
+            decrypt_original_detached(&delegating_sk, &capsule, &ciphertext_body[..10], &tag),
+            Err(DecryptionError::CiphertextTooShort)
+        );
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let verified_cfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
+            .collect();
+
+        let plaintext_bob = decrypt_reencrypted_detached(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags,
+            &ciphertext_body,
+            &tag,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        assert_eq!(
+            decrypt_reencrypted_detached(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &verified_cfrags,
+                &ciphertext_body,
+                &bad_tag,
+            ),
+            Err(ReencryptionError::OnDecryption(
+                DecryptionError::AuthenticationFailed
+            ))
+        );
+    }
+
+    #[test]
+    fn test_self_encrypt_decrypt() {
+        let delegating_sk = SecretKey::random();
+        assert!(self_encrypt_decrypt(&delegating_sk));
+
+        let mut rng = DummyRng::new(b"seed", b"self_encrypt_decrypt");
+        assert!(self_encrypt_decrypt_with_rng(&mut rng, &delegating_sk));
+        assert!(self_encrypt_decrypt_with_rng(&mut rng, &delegating_sk));
+
+        // A key restored from the serialized form works as well
+        let sk_back = SecretKey::from_bytes(delegating_sk.to_secret_array().as_secret()).unwrap();
+        assert!(self_encrypt_decrypt(&sk_back));
+    }
+
+    #[test]
+    fn test_validate_grant() {
+        let delegating_sk = SecretKey::random();
+        let signer = Signer::new(&SecretKey::random());
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+
+        let validate = |kfrags: &[_], threshold, maybe_receiving_sk| {
+            validate_grant(
+                &delegating_sk,
+                &receiving_pk,
+                maybe_receiving_sk,
+                &signer,
+                kfrags,
+                threshold,
+            )
+        };
+
+        // A correct grant passes with or without the receiving secret key
+        assert!(validate(&kfrags, 2, Some(&receiving_sk)));
+        assert!(validate(&kfrags, 2, None));
+        assert!(validate(&kfrags[..2], 2, Some(&receiving_sk)));
+
+        // Wrong threshold (only detectable with the receiving secret key)
+        assert!(!validate(&kfrags, 1, Some(&receiving_sk)));
+        assert!(!validate(&kfrags, 3, Some(&receiving_sk)));
+
+        // Not enough fragments, or none at all
+        assert!(!validate(&kfrags[..1], 2, None));
+        assert!(!validate(&[], 0, None));
+
+        // Repeating fragments and fragments of different delegations
+        assert!(!validate(
+            &[kfrags[0].clone(), kfrags[0].clone()],
+            2,
+            Some(&receiving_sk)
+        ));
+        let kfrags_other =
+            generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        assert!(!validate(
+            &[kfrags[0].clone(), kfrags_other[1].clone()],
+            2,
+            None
+        ));
+
+        // A receiving secret key not matching the receiving public key
+        let other_sk = SecretKey::random();
+        assert!(!validate(&kfrags, 2, Some(&other_sk)));
+
+        // Fragments of a delegation to a different receiver, or signed by a different signer
+        let kfrags_other_receiver = generate_kfrags(
+            &delegating_sk,
+            &other_sk.public_key(),
+            &signer,
+            2,
+            3,
+            true,
+            true,
+        );
+        assert!(!validate(&kfrags_other_receiver, 2, None));
+
+        let other_signer = Signer::new(&SecretKey::random());
+        let kfrags_other_signer = generate_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &other_signer,
+            2,
+            3,
+            true,
+            true,
+        );
+        assert!(!validate(&kfrags_other_signer, 2, None));
+
+        // Fragments of a delegation from a different key
+        let kfrags_other_delegator = generate_kfrags(
+            &SecretKey::random(),
+            &receiving_pk,
+            &signer,
+            2,
+            3,
+            true,
+            true,
+        );
+        assert!(!validate(&kfrags_other_delegator, 2, None));
+    }
+
+    #[test]
+    fn test_threshold_boundaries() {
+        let threshold: usize = 3;
+        let shares: usize = 5;
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+
+        let kfrags = generate_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            threshold,
+            shares,
+            true,
+            true,
+        );
+        let verified_cfrags: Vec<_> = kfrags
+            .iter()
+            .map(|vkfrag| reencrypt(&capsule, vkfrag))
+            .collect();
+
+        let decrypt = |cfrags: &[VerifiedCapsuleFrag]| {
+            decrypt_reencrypted(&receiving_sk, &delegating_pk, &capsule, cfrags, &ciphertext)
+        };
+
+        // Exactly the threshold, any subset of it
+        for start in 0..=(shares - threshold) {
+            let plaintext_bob = decrypt(&verified_cfrags[start..start + threshold]).unwrap();
+            assert_eq!(&plaintext_bob as &[u8], plaintext);
+        }
+        let scattered = [
+            verified_cfrags[4].clone(),
+            verified_cfrags[0].clone(),
+            verified_cfrags[2].clone(),
+        ];
+        let plaintext_bob = decrypt(&scattered).unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Over the threshold, up to all the shares
+        for number in (threshold + 1)..=shares {
+            let plaintext_bob = decrypt(&verified_cfrags[..number]).unwrap();
+            assert_eq!(&plaintext_bob as &[u8], plaintext);
+        }
+
+        // Under the threshold
+        for number in 1..threshold {
+            assert_eq!(
+                decrypt(&verified_cfrags[..number]),
+                Err(ReencryptionError::OnOpen(
+                    OpenReencryptedError::ValidationFailed
+                ))
+            );
+        }
+        assert_eq!(
+            decrypt(&[]),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::NoCapsuleFrags
+            ))
+        );
+
+        // Enough fragments, but one of them is repeated
+        let repeated = [
+            verified_cfrags[0].clone(),
+            verified_cfrags[1].clone(),
+            verified_cfrags[0].clone(),
+        ];
+        assert_eq!(
+            decrypt(&repeated),
+            Err(ReencryptionError::OnOpen(
+                OpenReencryptedError::RepeatingCapsuleFrags
+            ))
+        );
+    }
+
+    #[test]
+    fn test_deterministic_kfrags() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let labels = [&b"node-0"[..], b"node-1", b"node-2"];
+        let seed = b"delegation seed";
+
+        let kfrags = generate_deterministic_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+
+        // The fragment for the second node is lost and regenerated
+        let regenerated = generate_deterministic_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels[1..2],
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(regenerated.len(), 1);
+        assert_eq!(regenerated[0].kfrag.id, kfrags[1].kfrag.id);
+        assert_eq!(regenerated[0].kfrag.key, kfrags[1].kfrag.key);
+        assert_eq!(regenerated[0].kfrag.precursor, kfrags[1].kfrag.precursor);
+        assert!(delegations_equal(&regenerated, &kfrags[1..2]));
+
+        // The regenerated fragment can be used together with the original ones
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&delegating_pk, plaintext).unwrap();
+        let verified_cfrags = [
+            reencrypt(&capsule, &kfrags[0]),
+            reencrypt(&capsule, &regenerated[0]),
+        ];
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &verified_cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // A different seed, delegating key or receiving key results in a different delegation
+        let kfrags_other_seed = generate_deterministic_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            2,
+            b"other seed",
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_ne!(
+            kfrags_other_seed[0].kfrag.precursor,
+            kfrags[0].kfrag.precursor
+        );
+
+        let other_receiving_pk = SecretKey::random().public_key();
+        let kfrags_other_receiver = generate_deterministic_kfrags(
+            &delegating_sk,
+            &other_receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_ne!(
+            kfrags_other_receiver[0].kfrag.precursor,
+            kfrags[0].kfrag.precursor
+        );
+
+        let kfrags_other_delegator = generate_deterministic_kfrags(
+            &SecretKey::random(),
+            &receiving_pk,
+            &signer,
+            2,
+            seed,
+            &labels,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_ne!(
+            kfrags_other_delegator[0].kfrag.precursor,
+            kfrags[0].kfrag.precursor
+        );
+
+        // A fragment regenerated with a different threshold cannot be mixed with the original ones
+        let kfrags_other_threshold = generate_deterministic_kfrags(
+            &delegating_sk,
+            &receiving_pk,
+            &signer,
+            3,
+            seed,
+            &labels[1..2],
+            true,
+            true,
+        )
+        .unwrap();
+        assert_ne!(
+            kfrags_other_threshold[0].kfrag.precursor,
+            kfrags[0].kfrag.precursor
+        );
+        assert_eq!(
+            check_kfrags_consistency(&[kfrags[0].clone(), kfrags_other_threshold[0].clone()]),
+            Err(KeyFragSetError::MismatchedDelegations)
+        );
+
+        // Repeated labels
+        assert_eq!(
+            generate_deterministic_kfrags(
+                &delegating_sk,
+                &receiving_pk,
+                &signer,
+                2,
+                seed,
+                &[&b"node-0"[..], b"node-0"],
+                true,
+                true,
+            ),
+            Err(LabeledKeyFragsError::RepeatingLabels)
+        );
+    }
 }
@@ -0,0 +1,202 @@
+//! Encryption of records bound to a record ID and a schema version.
+//!
+//! The record ID and the schema version are authenticated along with the ciphertext,
+//! so a ciphertext stored under one record ID cannot be passed off as a different record,
+//! or as a record of a different schema version.
+//! The decryption fails with [`DecryptionError::AuthenticationFailed`]
+//! if any of them does not match the values used on encryption.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "default-rng")]
+use rand_core::OsRng;
+
+use crate::capsule::Capsule;
+use crate::capsule_frag::VerifiedCapsuleFrag;
+use crate::dem::{DecryptionError, EncryptionError, DEM};
+use crate::keys::{PublicKey, SecretKey};
+use crate::metrics::{record, Operation};
+use crate::pre::ReencryptionError;
+use crate::traits::SerializableToArray;
+
+fn record_aad(capsule: &Capsule, record_id: &[u8], schema_version: u32) -> Vec<u8> {
+    // The record ID is length-prefixed to make the encoding unambiguous.
+    let mut aad = Vec::<u8>::new();
+    aad.extend_from_slice(&capsule.to_array());
+    aad.extend_from_slice(b"RECORD");
+    aad.extend_from_slice(&(record_id.len() as u32).to_be_bytes());
+    aad.extend_from_slice(record_id);
+    aad.extend_from_slice(&schema_version.to_be_bytes());
+    aad
+}
+
+/// Encrypts the given plaintext message, binding it to `record_id` and `schema_version`.
+/// Returns the KEM [`Capsule`] and the ciphertext.
+///
+/// The capsule can be reencrypted as usual, but the ciphertext can only be decrypted
+/// with [`decrypt_original_record`] or [`decrypt_reencrypted_record`]
+/// given the same record ID and schema version.
+pub fn encrypt_record_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+    record_id: &[u8],
+    schema_version: u32,
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    let (capsule, key_seed) = Capsule::from_public_key(rng, delegating_pk);
+    let dem = DEM::new(&key_seed);
+    let aad = record_aad(&capsule, record_id, schema_version);
+    let result = dem
+        .encrypt(rng, plaintext, &aad)
+        .map(|ciphertext| (capsule, ciphertext));
+    record(Operation::Encrypt, result)
+}
+
+/// A synonym for [`encrypt_record_with_rng`] with the default RNG.
+#[cfg(feature = "default-rng")]
+pub fn encrypt_record(
+    delegating_pk: &PublicKey,
+    plaintext: &[u8],
+    record_id: &[u8],
+    schema_version: u32,
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    encrypt_record_with_rng(
+        &mut OsRng,
+        delegating_pk,
+        plaintext,
+        record_id,
+        schema_version,
+    )
+}
+
+/// Attempts to decrypt the ciphertext created by [`encrypt_record`]
+/// using the receiver's secret key.
+pub fn decrypt_original_record(
+    delegating_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+    record_id: &[u8],
+    schema_version: u32,
+) -> Result<Box<[u8]>, DecryptionError> {
+    let key_seed = capsule.open_original(delegating_sk);
+    let dem = DEM::new(&key_seed);
+    record(
+        Operation::Decrypt,
+        dem.decrypt(ciphertext, &record_aad(capsule, record_id, schema_version)),
+    )
+}
+
+/// Decrypts the ciphertext created by [`encrypt_record`]
+/// using previously reencrypted capsule fragments.
+///
+/// See [`decrypt_reencrypted`](`crate::decrypt_reencrypted`) for the details.
+pub fn decrypt_reencrypted_record(
+    receiving_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    verified_cfrags: &[VerifiedCapsuleFrag],
+    ciphertext: impl AsRef<[u8]>,
+    record_id: &[u8],
+    schema_version: u32,
+) -> Result<Box<[u8]>, ReencryptionError> {
+    let cfrags: Vec<_> = verified_cfrags
+        .iter()
+        .cloned()
+        .map(|vcfrag| vcfrag.cfrag)
+        .collect();
+    let result = capsule
+        .open_reencrypted(receiving_sk, delegating_pk, &cfrags)
+        .map_err(ReencryptionError::OnOpen)
+        .and_then(|key_seed| {
+            let dem = DEM::new(&key_seed);
+            dem.decrypt(&ciphertext, &record_aad(capsule, record_id, schema_version))
+                .map_err(ReencryptionError::OnDecryption)
+        });
+    record(Operation::Decrypt, result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::{decrypt_original_record, decrypt_reencrypted_record, encrypt_record};
+    use crate::{
+        decrypt_original, generate_kfrags, reencrypt, DecryptionError, ReencryptionError,
+        SecretKey, Signer,
+    };
+
+    #[test]
+    fn test_record_binding() {
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = delegating_sk.public_key();
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = receiving_sk.public_key();
+
+        let signer = Signer::new(&SecretKey::random());
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) =
+            encrypt_record(&delegating_pk, plaintext, b"record-1", 2).unwrap();
+
+        let plaintext_back =
+            decrypt_original_record(&delegating_sk, &capsule, &ciphertext, b"record-1", 2).unwrap();
+        assert_eq!(&plaintext_back as &[u8], plaintext);
+
+        // Different record ID
+        assert_eq!(
+            decrypt_original_record(&delegating_sk, &capsule, &ciphertext, b"record-2", 2),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+
+        // Different schema version
+        assert_eq!(
+            decrypt_original_record(&delegating_sk, &capsule, &ciphertext, b"record-1", 1),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+
+        // A record cannot be decrypted as a regular ciphertext
+        assert_eq!(
+            decrypt_original(&delegating_sk, &capsule, &ciphertext),
+            Err(DecryptionError::AuthenticationFailed)
+        );
+
+        // Reencryption works as usual
+        let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+        let vcfrags: Vec<_> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag))
+            .collect();
+
+        let plaintext_bob = decrypt_reencrypted_record(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &vcfrags,
+            &ciphertext,
+            b"record-1",
+            2,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        assert_eq!(
+            decrypt_reencrypted_record(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &vcfrags,
+                &ciphertext,
+                b"record-2",
+                2,
+            ),
+            Err(ReencryptionError::OnDecryption(
+                DecryptionError::AuthenticationFailed
+            ))
+        );
+    }
+}
@@ -24,6 +24,7 @@ See https://github.com/iqlusioninc/crates/issues/757
 */
 
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 
 use generic_array::{ArrayLength, GenericArray};
 use zeroize::Zeroize;
@@ -61,6 +62,12 @@ where
     }
 }
 
+impl CanBeZeroizedOnDrop for Vec<u8> {
+    fn ensure_zeroized_on_drop(&mut self) {
+        self.zeroize()
+    }
+}
+
 /// A container for secret data.
 /// Makes the usage of secret data explicit and easy to track,
 /// prevents the secret data from being put on stack,
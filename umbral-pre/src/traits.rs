@@ -69,11 +69,17 @@ impl fmt::Display for SizeMismatchError {
 
 /// Errors that can happen during object deserialization.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum DeserializationError {
     /// Failed to construct the object from a given bytestring (with the correct length).
     ConstructionFailure(ConstructionError),
     /// The given bytestring is too short or too long.
     SizeMismatch(SizeMismatchError),
+    /// The given bytestring has a format version tag (contained in the variant)
+    /// not supported by this version of the library.
+    /// Only returned for the formats that include a version tag;
+    /// the serialization of individual objects does not have it.
+    UnsupportedVersion(u8),
 }
 
 impl fmt::Display for DeserializationError {
@@ -81,6 +87,9 @@ impl fmt::Display for DeserializationError {
         match self {
             Self::ConstructionFailure(err) => write!(f, "{}", err),
             Self::SizeMismatch(err) => write!(f, "{}", err),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version: {}", version)
+            }
         }
     }
 }
@@ -320,7 +329,7 @@ mod tests {
         let s_from_arr = SomeStruct::from_array(&s_arr).unwrap();
         assert_eq!(s_from_arr, s);
 
-        let s_from_bytes = SomeStruct::from_bytes(&s_arr_ref).unwrap();
+        let s_from_bytes = SomeStruct::from_bytes(s_arr_ref).unwrap();
         assert_eq!(s_from_bytes, s);
     }
 
@@ -328,7 +337,7 @@ mod tests {
     fn test_invalid_data() {
         // invalid value for `f4` (`bool` must be either 0 or 1)
         let s_arr: [u8; 6] = [0x00, 0x01, 0x02, 0x00, 0x03, 0x02];
-        let s = SomeStruct::from_bytes(&s_arr);
+        let s = SomeStruct::from_bytes(s_arr);
         assert_eq!(
             s,
             Err(DeserializationError::ConstructionFailure(
@@ -341,7 +350,7 @@ mod tests {
     fn test_invalid_length() {
         // An excessive byte at the end
         let s_arr: [u8; 7] = [0x00, 0x01, 0x02, 0x00, 0x03, 0x01, 0x00];
-        let s = SomeStruct::from_bytes(&s_arr);
+        let s = SomeStruct::from_bytes(s_arr);
         assert_eq!(
             s,
             Err(DeserializationError::SizeMismatch(SizeMismatchError::new(
@@ -0,0 +1,40 @@
+//! The operation counters are global, so the checks that they stay unchanged
+//! are in a separate test binary (and therefore a separate process),
+//! where no other tests can perform operations concurrently.
+
+#![cfg(feature = "metrics")]
+
+use umbral_pre::{
+    encrypt, generate_kfrags, is_owner, metrics, self_encrypt_decrypt, validate_grant, SecretKey,
+    Signer,
+};
+
+#[test]
+fn test_internal_operations_are_not_counted() {
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = delegating_sk.public_key();
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = receiving_sk.public_key();
+    let signer = Signer::new(&SecretKey::random());
+
+    let (capsule, ciphertext) = encrypt(&delegating_pk, b"peace at dawn").unwrap();
+    let kfrags = generate_kfrags(&delegating_sk, &receiving_pk, &signer, 2, 3, true, true);
+
+    let before = metrics();
+
+    assert!(is_owner(&delegating_sk, &capsule, &ciphertext));
+    assert!(!is_owner(&receiving_sk, &capsule, &ciphertext));
+    assert_eq!(metrics().decrypt, before.decrypt);
+
+    assert!(self_encrypt_decrypt(&delegating_sk));
+    assert!(validate_grant(
+        &delegating_sk,
+        &receiving_pk,
+        Some(&receiving_sk),
+        &signer,
+        &kfrags,
+        2
+    ));
+
+    assert_eq!(metrics(), before);
+}